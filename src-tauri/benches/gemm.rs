@@ -0,0 +1,66 @@
+//! Square f32 matrix products against a plain triple loop.
+//!
+//! `cargo bench --bench gemm`, add `--features simd` or `--features parallel`
+//! to compare the kernels.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use tresh::neural_network::Matrix;
+
+fn sample(rows: usize, columns: usize) -> Matrix<f32> {
+    Matrix::from_fn(rows, columns, |r, c| {
+        ((r * columns + c) * 7 % 11) as f32 / 11. - 0.5
+    })
+}
+
+fn naive(c: &mut Matrix<f32>, a: &Matrix<f32>, b: &Matrix<f32>) {
+    for row in 0..c.rows {
+        for col in 0..c.columns {
+            let mut sum = 0.;
+            for k in 0..a.columns {
+                sum += a.data[row * a.columns + k] * b.data[k * b.columns + col];
+            }
+            c.data[row * c.columns + col] = sum;
+        }
+    }
+}
+
+/// Mean time of `f` over enough runs to take about half a second.
+fn time(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed() < Duration::from_millis(500) {
+        f();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn main() {
+    for size in [64, 256, 1024] {
+        let a = sample(size, size);
+        let b = sample(size, size);
+        let mut bt = Matrix::new(size, size);
+        for row in 0..size {
+            for col in 0..size {
+                bt.set(col, row, b.get(row, col));
+            }
+        }
+        let mut c = Matrix::new(size, size);
+
+        let naive = time(|| naive(black_box(&mut c), &a, &b));
+        let blocked = time(|| black_box(&mut c).dot_into(&a, &b));
+        let dot = time(|| black_box(&mut c).dot_transposed_into(&a, &bt));
+
+        println!(
+            "{0}x{0}: naive {1:?}, dot_into {2:?} ({3:.1}x), dot_transposed_into {4:?} ({5:.1}x)",
+            size,
+            naive,
+            blocked,
+            naive.as_secs_f64() / blocked.as_secs_f64(),
+            dot,
+            naive.as_secs_f64() / dot.as_secs_f64(),
+        );
+    }
+}
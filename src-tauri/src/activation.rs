@@ -0,0 +1,233 @@
+use crate::error::DerivativeError;
+use crate::float::Float;
+use crate::neural_network::{sigmoid, Matrix};
+use crate::reductions::Reduce;
+
+/// Nonlinearity applied to the pre-activations `z` of a layer.
+///
+/// Elementwise functions only implement `forward` and `derivative`, functions
+/// that mix the elements of a row, like softmax, override `apply` and `backward`.
+pub trait Activation<T: Float>: std::fmt::Debug + Send + Sync {
+    fn forward(&self, x: T) -> T;
+
+    /// `d forward / dx` at `x`.
+    fn derivative(&self, x: T) -> T;
+
+    /// Writes the activations of `z` into `a`.
+    fn apply(&self, z: &Matrix<T>, a: &mut Matrix<T>) {
+        a.zip_with_inplace(z, |_, x| self.forward(x));
+    }
+
+    /// Turns `grad`, the cost gradient with respect to the activations `a`,
+    /// into the gradient with respect to the pre-activations `z`.
+    fn backward(&self, z: &Matrix<T>, _a: &Matrix<T>, grad: &mut Matrix<T>) {
+        grad.zip_with_inplace(z, |g, x| g * self.derivative(x));
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Checks `backward` against central differences of `apply` at a spread of
+/// points in `-3..3`, the tolerance is relative for derivatives above 1.
+pub fn check_derivative<T: Float>(
+    activation: &dyn Activation<T>,
+    step: T,
+    tolerance: T,
+) -> Result<(), DerivativeError> {
+    const POINTS: usize = 16;
+
+    // offset by half a cell so no point lands on a kink at 0
+    let z = Matrix::from_fn(1, POINTS, |_, i| {
+        T::from_f64(6. * (i as f64 + 0.5) / POINTS as f64 - 3.)
+    });
+    // uneven upstream gradient so row-mixing activations are exercised
+    let upstream = Matrix::from_fn(1, POINTS, |_, i| T::from_f64(1. + i as f64 / POINTS as f64));
+    let cost = |z: &Matrix<T>| {
+        let mut a = Matrix::new(1, POINTS);
+        activation.apply(z, &mut a);
+        a.data
+            .iter()
+            .zip(&upstream.data)
+            .map(|(&a, &g)| a * g)
+            .sum::<T>()
+    };
+
+    let mut a = Matrix::new(1, POINTS);
+    activation.apply(&z, &mut a);
+    let mut analytic = upstream.clone();
+    activation.backward(&z, &a, &mut analytic);
+
+    for i in 0..POINTS {
+        let mut shifted = z.clone();
+        shifted.data[i] = z.data[i] + step;
+        let plus = cost(&shifted);
+        shifted.data[i] = z.data[i] - step;
+        let minus = cost(&shifted);
+        let numeric = (plus - minus) / (step + step);
+
+        let error = (numeric - analytic.data[i]).abs();
+        if error > tolerance * numeric.abs().max(T::ONE) || !error.is_finite() {
+            return Err(DerivativeError {
+                name: activation.name(),
+                at: z.data[i].to_f64(),
+                numeric: numeric.to_f64(),
+                analytic: analytic.data[i].to_f64(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Activations that can be named directly in the `arch` of `NeuralNetwork::new`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Builtin {
+    #[default]
+    Sigmoid,
+    Tanh,
+    Relu,
+    /// Slope for negative inputs.
+    LeakyRelu(f64),
+    /// `alpha * (e^x - 1)` for negative inputs.
+    Elu(f64),
+    Linear,
+    /// Normalized exponentials over each row. Only `apply` and `backward`
+    /// are defined, `forward` and `derivative` panic since no single element
+    /// determines the output.
+    Softmax,
+}
+
+impl<T: Float> Activation<T> for Builtin {
+    fn forward(&self, x: T) -> T {
+        match *self {
+            Builtin::Sigmoid => sigmoid(x),
+            Builtin::Tanh => x.tanh(),
+            Builtin::Relu => x.max(T::ZERO),
+            Builtin::LeakyRelu(_) | Builtin::Elu(_) if x > T::ZERO => x,
+            Builtin::LeakyRelu(slope) => T::from_f64(slope) * x,
+            Builtin::Elu(alpha) => T::from_f64(alpha) * (x.exp() - T::ONE),
+            Builtin::Linear => x,
+            Builtin::Softmax => unreachable!("softmax is applied per row"),
+        }
+    }
+
+    fn derivative(&self, x: T) -> T {
+        match *self {
+            Builtin::Sigmoid => {
+                let s = sigmoid(x);
+                s * (T::ONE - s)
+            }
+            Builtin::Tanh => T::ONE - x.tanh() * x.tanh(),
+            Builtin::Relu | Builtin::LeakyRelu(_) | Builtin::Elu(_) if x > T::ZERO => T::ONE,
+            Builtin::Relu => T::ZERO,
+            Builtin::LeakyRelu(slope) => T::from_f64(slope),
+            Builtin::Elu(alpha) => T::from_f64(alpha) * x.exp(),
+            Builtin::Linear => T::ONE,
+            Builtin::Softmax => unreachable!("softmax is applied per row"),
+        }
+    }
+
+    fn apply(&self, z: &Matrix<T>, a: &mut Matrix<T>) {
+        if *self != Builtin::Softmax {
+            a.zip_with_inplace(z, |_, x| self.forward(x));
+            return;
+        }
+
+        a.copy_from(z);
+        for row in 0..a.rows {
+            // shift by the max so exp cannot overflow
+            let max = a.row(row).max().unwrap_or(T::ZERO);
+            let mut row = a.row_mut(row);
+            row.map_inplace(|x| (x - max).exp());
+            let sum = row.sum();
+            row.map_inplace(|x| x / sum);
+        }
+    }
+
+    fn backward(&self, z: &Matrix<T>, a: &Matrix<T>, grad: &mut Matrix<T>) {
+        if *self != Builtin::Softmax {
+            grad.zip_with_inplace(z, |g, x| g * self.derivative(x));
+            return;
+        }
+
+        // softmax jacobian: dz_j = a_j * (da_j - sum_k da_k * a_k)
+        for row in 0..a.rows {
+            let a = a.row(row);
+            let dot: T = grad.row(row).iter().zip(a.iter()).map(|(g, a)| g * a).sum();
+            grad.row_mut(row).zip_with_inplace(&a, |g, a| a * (g - dot));
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Builtin::Sigmoid => "sigmoid".to_string(),
+            Builtin::Tanh => "tanh".to_string(),
+            Builtin::Relu => "relu".to_string(),
+            Builtin::LeakyRelu(slope) => format!("leaky relu {}", slope),
+            Builtin::Elu(alpha) => format!("elu {}", alpha),
+            Builtin::Linear => "linear".to_string(),
+            Builtin::Softmax => "softmax".to_string(),
+        }
+    }
+}
+
+/// `x * sigmoid(x)`, a custom activation for tests.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct Swish;
+
+#[cfg(test)]
+impl<T: Float> Activation<T> for Swish {
+    fn forward(&self, x: T) -> T {
+        x * sigmoid(x)
+    }
+
+    fn derivative(&self, x: T) -> T {
+        let s = sigmoid(x);
+        s + x * s * (T::ONE - s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct WrongSwish;
+
+    impl<T: Float> Activation<T> for WrongSwish {
+        fn forward(&self, x: T) -> T {
+            x * sigmoid(x)
+        }
+
+        fn derivative(&self, x: T) -> T {
+            sigmoid(x)
+        }
+    }
+
+    #[test]
+    fn test_check_derivative() {
+        use Builtin::*;
+
+        for builtin in [
+            Sigmoid,
+            Tanh,
+            Relu,
+            LeakyRelu(0.1),
+            Elu(1.),
+            Linear,
+            Softmax,
+        ] {
+            check_derivative::<f64>(&builtin, 1e-5, 1e-6).unwrap();
+            check_derivative::<f32>(&builtin, 1e-2, 1e-2).unwrap();
+        }
+        check_derivative::<f64>(&Swish, 1e-5, 1e-6).unwrap();
+        // softmax has no elementwise form
+        assert!(std::panic::catch_unwind(|| Activation::<f64>::forward(&Softmax, 0.)).is_err());
+
+        let err = check_derivative::<f64>(&WrongSwish, 1e-5, 1e-6).unwrap_err();
+        assert_eq!((err.name.as_str(), err.at), ("WrongSwish", -2.8125));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::error::ShapeError;
+use crate::float::Float;
+use crate::matrix_view::MatrixView;
+use crate::neural_network::Matrix;
+
+impl<T: Float> Matrix<T> {
+    pub fn identity(n: usize) -> Matrix<T> {
+        Matrix::from_fn(n, n, |row, col| if row == col { T::ONE } else { T::ZERO })
+    }
+
+    /// Matrix with `f(row, col)` at every position, filled in row-major order.
+    pub fn from_fn(rows: usize, columns: usize, mut f: impl FnMut(usize, usize) -> T) -> Matrix<T> {
+        let mut data = Vec::with_capacity(rows * columns);
+        for row in 0..rows {
+            for col in 0..columns {
+                data.push(f(row, col));
+            }
+        }
+
+        Matrix {
+            rows,
+            columns,
+            data,
+        }
+    }
+
+    /// Copies a row-major slice holding exactly `rows * columns` elements.
+    pub fn try_from_row_slice(
+        rows: usize,
+        columns: usize,
+        data: &[T],
+    ) -> Result<Matrix<T>, ShapeError> {
+        ShapeError::check("from_row_slice", (1, rows * columns), (1, data.len()))?;
+
+        Ok(Matrix {
+            rows,
+            columns,
+            data: data.to_vec(),
+        })
+    }
+
+    pub fn from_row_slice(rows: usize, columns: usize, data: &[T]) -> Matrix<T> {
+        Matrix::try_from_row_slice(rows, columns, data).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// One matrix row per item, every row must be as long as the first.
+    /// No rows gives a 0x0 matrix.
+    pub fn try_from_rows<R: AsRef<[T]>>(
+        rows: impl IntoIterator<Item = R>,
+    ) -> Result<Matrix<T>, ShapeError> {
+        let mut result = Matrix::new(0, 0);
+
+        for row in rows {
+            let row = row.as_ref();
+            if result.rows == 0 {
+                result.columns = row.len();
+            }
+            ShapeError::check("from_rows", (1, result.columns), (1, row.len()))?;
+
+            result.data.extend_from_slice(row);
+            result.rows += 1;
+        }
+
+        Ok(result)
+    }
+
+    pub fn from_rows<R: AsRef<[T]>>(rows: impl IntoIterator<Item = R>) -> Matrix<T> {
+        Matrix::try_from_rows(rows).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Places `parts` side by side, they must all have the same number of rows.
+    pub fn try_hstack(parts: &[MatrixView<T>]) -> Result<Matrix<T>, ShapeError> {
+        let rows = parts.first().map_or(0, |part| part.rows);
+        let mut columns = 0;
+        for part in parts {
+            ShapeError::check("hstack", (rows, part.columns), part.shape())?;
+            columns += part.columns;
+        }
+
+        let mut result = Matrix::new(rows, columns);
+        let mut col = 0;
+        for part in parts {
+            result.block_mut(0, col, rows, part.columns).copy_from(part);
+            col += part.columns;
+        }
+
+        Ok(result)
+    }
+
+    pub fn hstack(parts: &[MatrixView<T>]) -> Matrix<T> {
+        Matrix::try_hstack(parts).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Places `parts` on top of each other, they must all have the same
+    /// number of columns.
+    pub fn try_vstack(parts: &[MatrixView<T>]) -> Result<Matrix<T>, ShapeError> {
+        let columns = parts.first().map_or(0, |part| part.columns);
+        let mut result = Matrix::new(0, columns);
+        for part in parts {
+            ShapeError::check("vstack", (part.rows, columns), part.shape())?;
+            result.data.extend(part.iter());
+            result.rows += part.rows;
+        }
+
+        Ok(result)
+    }
+
+    pub fn vstack(parts: &[MatrixView<T>]) -> Matrix<T> {
+        Matrix::try_vstack(parts).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Views of the rows before and from `row`, the error reports the
+    /// smallest shape that could be split there.
+    pub fn try_split_rows(
+        &self,
+        row: usize,
+    ) -> Result<(MatrixView<'_, T>, MatrixView<'_, T>), ShapeError> {
+        if row > self.rows {
+            return Err(ShapeError {
+                op: "split_rows",
+                expected: (row, self.columns),
+                actual: self.shape(),
+            });
+        }
+
+        Ok((
+            self.block(0, 0, row, self.columns),
+            self.block(row, 0, self.rows - row, self.columns),
+        ))
+    }
+
+    pub fn split_rows(&self, row: usize) -> (MatrixView<'_, T>, MatrixView<'_, T>) {
+        self.try_split_rows(row)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Views of the columns before and from `col`, see `try_split_rows`.
+    pub fn try_split_columns(
+        &self,
+        col: usize,
+    ) -> Result<(MatrixView<'_, T>, MatrixView<'_, T>), ShapeError> {
+        if col > self.columns {
+            return Err(ShapeError {
+                op: "split_columns",
+                expected: (self.rows, col),
+                actual: self.shape(),
+            });
+        }
+
+        Ok((
+            self.block(0, 0, self.rows, col),
+            self.block(0, col, self.rows, self.columns - col),
+        ))
+    }
+
+    pub fn split_columns(&self, col: usize) -> (MatrixView<'_, T>, MatrixView<'_, T>) {
+        self.try_split_columns(col)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix_view::AsMatrixView;
+
+    #[test]
+    fn test_constructors() {
+        let eye: Matrix = Matrix::identity(2);
+        assert_eq!(eye.data, vec![1., 0., 0., 1.]);
+
+        let m: Matrix = Matrix::from_fn(2, 3, |r, c| (r * 3 + c) as f32);
+        assert_eq!(
+            m.data,
+            Matrix::from_row_slice(2, 3, &[0., 1., 2., 3., 4., 5.]).data
+        );
+        assert_eq!(m.data, Matrix::from_rows([[0., 1., 2.], [3., 4., 5.]]).data);
+        assert!(Matrix::<f32>::try_from_row_slice(2, 2, &[1., 2., 3.]).is_err());
+
+        let err = Matrix::<f32>::try_from_rows(vec![vec![1., 2.], vec![3.]]).unwrap_err();
+        assert_eq!((err.expected, err.actual), ((1, 2), (1, 1)));
+        assert_eq!(Matrix::<f32>::from_2d_vec(&vec![]).shape(), (0, 0));
+
+        let (left, right) = m.split_columns(1);
+        assert_eq!(right.to_matrix().data, vec![1., 2., 4., 5.]);
+        assert_eq!(Matrix::hstack(&[left, right]).data, m.data);
+        assert!(m.try_split_columns(4).is_err());
+
+        let (top, bottom) = m.split_rows(1);
+        assert_eq!(
+            Matrix::vstack(&[bottom, top]).data,
+            vec![3., 4., 5., 0., 1., 2.]
+        );
+        assert!(Matrix::try_vstack(&[bottom, eye.view()]).is_err());
+        assert!(Matrix::try_hstack(&[bottom, m.view()]).is_err());
+    }
+}
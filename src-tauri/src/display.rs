@@ -0,0 +1,159 @@
+//! Text and HTML rendering of matrices and networks.
+//!
+//! `{}` uses `MatrixFormat::default()`, `{:.N}` overrides the precision.
+
+use crate::float::Float;
+use crate::matrix_view::{AsMatrixView, MatrixView};
+use std::fmt;
+
+const ELLIPSIS: &str = "…";
+
+#[derive(Debug, Clone, Copy)]
+pub struct MatrixFormat {
+    /// Digits after the decimal point.
+    pub precision: usize,
+    /// Larger matrices only show their first and last rows/columns,
+    /// with `…` in between.
+    pub max_rows: usize,
+    pub max_columns: usize,
+}
+
+impl Default for MatrixFormat {
+    fn default() -> Self {
+        MatrixFormat {
+            precision: 2,
+            max_rows: 10,
+            max_columns: 10,
+        }
+    }
+}
+
+/// Indices shown along one axis of length `len`, `None` marks the elided part.
+fn visible(len: usize, max: usize) -> Vec<Option<usize>> {
+    if len <= max {
+        return (0..len).map(Some).collect();
+    }
+
+    let tail = max / 2;
+    let head = max - tail;
+    (0..head)
+        .map(Some)
+        .chain(std::iter::once(None))
+        .chain((len - tail..len).map(Some))
+        .collect()
+}
+
+impl MatrixFormat {
+    /// Visible cells as strings, row by row.
+    fn cells<T: Float>(&self, view: MatrixView<T>) -> Vec<Vec<String>> {
+        let columns = visible(view.columns, self.max_columns);
+
+        visible(view.rows, self.max_rows)
+            .into_iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|&col| match (row, col) {
+                        (Some(row), Some(col)) => {
+                            format!("{:.*}", self.precision, view.get(row, col))
+                        }
+                        _ => ELLIPSIS.to_string(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Right-justified columns, one line per row.
+    pub fn render<T: Float>(&self, m: &impl AsMatrixView<T>) -> String {
+        let cells = self.cells(m.view());
+        let columns = cells.first().map_or(0, |row| row.len());
+        let widths: Vec<usize> = (0..columns)
+            .map(|col| {
+                cells
+                    .iter()
+                    .map(|row| row[col].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&widths)
+                    .map(|(cell, &width)| format!("{:>width$}", cell, width = width))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// `<table class="matrix">` on a single line, for the `print` event panel.
+    pub fn render_html<T: Float>(&self, m: &impl AsMatrixView<T>) -> String {
+        let mut html = String::from("<table class=\"matrix\">");
+        for row in self.cells(m.view()) {
+            html += "<tr>";
+            for cell in row {
+                html += &format!("<td>{}</td>", cell);
+            }
+            html += "</tr>";
+        }
+        html += "</table>";
+        html
+    }
+
+    pub(crate) fn from_formatter(f: &fmt::Formatter<'_>) -> Self {
+        let default = MatrixFormat::default();
+        MatrixFormat {
+            precision: f.precision().unwrap_or(default.precision),
+            ..default
+        }
+    }
+}
+
+impl<T: Float> fmt::Display for MatrixView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&MatrixFormat::from_formatter(f).render(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neural_network::{Matrix, NeuralNetwork};
+
+    #[test]
+    fn test_matrix_display() {
+        let m: Matrix = Matrix::from_2d_vec(&vec![
+            vec![1., -20.5], //
+            vec![300., 0.25],
+        ]);
+        assert_eq!(format!("{}", m), "  1.00 -20.50\n300.00   0.25");
+        assert_eq!(format!("{:.0}", m.row(1)), "300 0");
+
+        let big: Matrix = Matrix::from_fn(12, 12, |r, c| (r * 12 + c) as f32);
+        let text = format!("{:.0}", big);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>()[4..7],
+            ["4", "…", "7"]
+        );
+        assert!(lines[5].split_whitespace().all(|cell| cell == "…"));
+
+        let html = MatrixFormat::default().render_html(&m.row(0));
+        assert_eq!(
+            html,
+            "<table class=\"matrix\"><tr><td>1.00</td><td>-20.50</td></tr></table>"
+        );
+
+        let nn: NeuralNetwork = NeuralNetwork::new(&[2, 1]);
+        assert_eq!(
+            format!("{:.1}", nn),
+            "layer 1: 2 -> 1, sigmoid\nw\n0.0\n0.0\nb\n0.0"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Serde support and a compact binary encoding for `Matrix`.
+//!
+//! Binary layout, all little-endian:
+//! `rows: u64`, `columns: u64`, then `rows * columns` elements in row-major order.
+
+use crate::error::{DecodeError, ShapeError};
+use crate::float::Float;
+use crate::neural_network::Matrix;
+use serde::Deserialize;
+
+const HEADER: usize = 2 * std::mem::size_of::<u64>();
+
+/// Unchecked `Matrix` fields as they come out of a deserializer.
+#[derive(Deserialize)]
+pub(crate) struct MatrixData<T> {
+    rows: usize,
+    columns: usize,
+    data: Vec<T>,
+}
+
+impl<T> TryFrom<MatrixData<T>> for Matrix<T> {
+    type Error = ShapeError;
+
+    fn try_from(raw: MatrixData<T>) -> Result<Self, Self::Error> {
+        let len = raw.rows.saturating_mul(raw.columns);
+        ShapeError::check("deserialize", (1, len), (1, raw.data.len()))?;
+
+        Ok(Matrix {
+            rows: raw.rows,
+            columns: raw.columns,
+            data: raw.data,
+        })
+    }
+}
+
+impl<T: Float> Matrix<T> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER + self.data.len() * T::BYTES);
+        bytes.extend_from_slice(&(self.rows as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.columns as u64).to_le_bytes());
+        for &value in &self.data {
+            value.write_le(&mut bytes);
+        }
+        bytes
+    }
+
+    /// Reads what `to_bytes` wrote, the byte count must match the header
+    /// exactly, which also catches decoding `f64` data as `f32`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Matrix<T>, DecodeError> {
+        if bytes.len() < HEADER {
+            return Err(DecodeError::MissingHeader { len: bytes.len() });
+        }
+
+        let read_u64 = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let rows = read_u64(0) as usize;
+        let columns = read_u64(8) as usize;
+
+        let expected = rows
+            .checked_mul(columns)
+            .and_then(|len| len.checked_mul(T::BYTES))
+            .and_then(|len| len.checked_add(HEADER))
+            .unwrap_or(usize::MAX);
+        if bytes.len() != expected {
+            return Err(DecodeError::Length {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        Ok(Matrix {
+            rows,
+            columns,
+            data: bytes[HEADER..]
+                .chunks_exact(T::BYTES)
+                .map(T::read_le)
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_encoding() {
+        let m: Matrix = Matrix::from_fn(2, 3, |r, c| r as f32 - c as f32 * 0.5);
+
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(
+            json,
+            r#"{"rows":2,"columns":3,"data":[0.0,-0.5,-1.0,1.0,0.5,0.0]}"#
+        );
+        let back: Matrix = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.shape(), back.data), (m.shape(), m.data.clone()));
+        let ragged = r#"{"rows":2,"columns":3,"data":[1.0]}"#;
+        assert!(serde_json::from_str::<Matrix>(ragged).is_err());
+
+        let bytes = m.to_bytes();
+        assert_eq!(bytes.len(), 16 + 6 * 4);
+        assert_eq!(Matrix::<f32>::from_bytes(&bytes).unwrap().data, m.data);
+        assert_eq!(
+            Matrix::<f64>::from_bytes(&bytes).unwrap_err(),
+            DecodeError::Length {
+                expected: 64,
+                actual: 40
+            }
+        );
+        assert_eq!(
+            Matrix::<f32>::from_bytes(&bytes[..5]).unwrap_err(),
+            DecodeError::MissingHeader { len: 5 }
+        );
+    }
+}
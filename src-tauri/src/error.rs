@@ -0,0 +1,190 @@
+/// Operand dimensions did not line up for the named operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShapeError {
+    pub op: &'static str,
+    pub expected: (usize, usize),
+    pub actual: (usize, usize),
+}
+
+impl ShapeError {
+    pub fn check(
+        op: &'static str,
+        expected: (usize, usize),
+        actual: (usize, usize),
+    ) -> Result<(), ShapeError> {
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(ShapeError {
+                op,
+                expected,
+                actual,
+            })
+        }
+    }
+}
+
+impl std::fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: expected shape {}x{}, got {}x{}",
+            self.op, self.expected.0, self.expected.1, self.actual.0, self.actual.1
+        )
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+/// Bytes given to `Matrix::from_bytes` do not hold a matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Fewer bytes than the shape header.
+    MissingHeader { len: usize },
+    /// Byte count does not match the shape in the header.
+    Length { expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::MissingHeader { len } => {
+                write!(f, "decode: {} bytes is too short for a shape header", len)
+            }
+            DecodeError::Length { expected, actual } => {
+                write!(f, "decode: expected {} bytes, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Tensor shapes or axes did not line up for the named operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TensorError {
+    pub op: &'static str,
+    pub expected: Vec<usize>,
+    pub actual: Vec<usize>,
+}
+
+impl std::fmt::Display for TensorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: expected {:?}, got {:?}",
+            self.op, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for TensorError {}
+
+/// A decomposition or solve could not be carried out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinalgError {
+    Shape(ShapeError),
+    /// Pivot `column` was zero up to rounding, the system has no unique solution.
+    Singular {
+        column: usize,
+    },
+}
+
+impl From<ShapeError> for LinalgError {
+    fn from(err: ShapeError) -> Self {
+        LinalgError::Shape(err)
+    }
+}
+
+impl std::fmt::Display for LinalgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinalgError::Shape(err) => err.fmt(f),
+            LinalgError::Singular { column } => {
+                write!(f, "matrix is singular, zero pivot in column {}", column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LinalgError {}
+
+/// A schedule was built with a step count of 0, such as the `period` of
+/// `CosineRestarts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleError {
+    pub schedule: &'static str,
+    pub field: &'static str,
+}
+
+impl ScheduleError {
+    /// Ok when `steps` is at least 1.
+    pub fn check(
+        schedule: &'static str,
+        field: &'static str,
+        steps: usize,
+    ) -> Result<(), ScheduleError> {
+        if steps > 0 {
+            Ok(())
+        } else {
+            Err(ScheduleError { schedule, field })
+        }
+    }
+}
+
+impl std::fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} must be at least 1", self.schedule, self.field)
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// `Sequential::try_fit` could not start training.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainError {
+    Shape(ShapeError),
+    /// `TrainConfig::batch_size` was 0.
+    ZeroBatchSize,
+    /// The dataset had no rows, so there is no loss to report.
+    EmptyDataset,
+}
+
+impl From<ShapeError> for TrainError {
+    fn from(err: ShapeError) -> Self {
+        TrainError::Shape(err)
+    }
+}
+
+impl std::fmt::Display for TrainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrainError::Shape(err) => err.fmt(f),
+            TrainError::ZeroBatchSize => write!(f, "fit: batch size must be at least 1"),
+            TrainError::EmptyDataset => write!(f, "fit: dataset has no rows"),
+        }
+    }
+}
+
+impl std::error::Error for TrainError {}
+
+/// An activation's derivative disagreed with finite differences of its forward pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivativeError {
+    pub name: String,
+    pub at: f64,
+    pub numeric: f64,
+    pub analytic: f64,
+}
+
+impl std::fmt::Display for DerivativeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: derivative at {} is {}, finite differences give {}",
+            self.name, self.at, self.analytic, self.numeric
+        )
+    }
+}
+
+impl std::error::Error for DerivativeError {}
@@ -0,0 +1,193 @@
+use rand::distributions::uniform::SampleUniform;
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Element type of a `Matrix`, implemented for `f32` and `f64`.
+pub trait Float:
+    Copy
+    + Debug
+    + Display
+    + Default
+    + PartialEq
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Sum
+    + SampleUniform
+    + Send
+    + Sync
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+    /// Gap between 1 and the next representable value.
+    const EPSILON: Self;
+
+    /// Size of the little-endian encoding.
+    const BYTES: usize;
+
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+
+    fn write_le(self, out: &mut Vec<u8>);
+    /// `bytes` must be exactly `BYTES` long.
+    fn read_le(bytes: &[u8]) -> Self;
+
+    fn from_usize(value: usize) -> Self {
+        Self::from_f64(value as f64)
+    }
+
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn tanh(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn is_finite(self) -> bool;
+
+    /// -1, 0 or 1. Unlike `f64::signum`, zero maps to 0, the subgradient used
+    /// for absolute values.
+    fn sign(self) -> Self {
+        if self > Self::ZERO {
+            Self::ONE
+        } else if self < Self::ZERO {
+            -Self::ONE
+        } else {
+            Self::ZERO
+        }
+    }
+
+    /// `dst[i] += scale * src[i]`, the inner loop of the matmul kernel.
+    fn mul_add_slice(_cpu: Cpu, dst: &mut [Self], scale: Self, src: &[Self]) {
+        for (d, &s) in dst.iter_mut().zip(src) {
+            *d += scale * s;
+        }
+    }
+
+    /// Sum of `a[i] * b[i]`.
+    fn dot_slice(_cpu: Cpu, a: &[Self], b: &[Self]) -> Self {
+        // independent accumulators so the compiler can keep them in vector lanes
+        let mut acc = [Self::ZERO; 8];
+        let chunks_a = a.chunks_exact(8);
+        let chunks_b = b.chunks_exact(8);
+        let tail: Self = chunks_a
+            .remainder()
+            .iter()
+            .zip(chunks_b.remainder())
+            .map(|(&x, &y)| x * y)
+            .sum();
+
+        for (ca, cb) in chunks_a.zip(chunks_b) {
+            for ((lane, &x), &y) in acc.iter_mut().zip(ca).zip(cb) {
+                *lane += x * y;
+            }
+        }
+
+        acc.into_iter().sum::<Self>() + tail
+    }
+}
+
+/// CPU features the slice kernels may use, checked once per matrix product
+/// rather than on every slice.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cpu {
+    /// Only ever set with the `simd` feature.
+    pub(crate) avx_fma: bool,
+}
+
+impl Cpu {
+    pub fn detect() -> Cpu {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        let avx_fma = crate::simd::has_avx_fma();
+        #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+        let avx_fma = false;
+
+        Cpu { avx_fma }
+    }
+}
+
+macro_rules! impl_float {
+    ($t:ty, $mul_add_slice:ident, $dot_slice:ident) => {
+        impl Float for $t {
+            const ZERO: Self = 0.;
+            const ONE: Self = 1.;
+            const EPSILON: Self = <$t>::EPSILON;
+            const BYTES: usize = std::mem::size_of::<$t>();
+
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn write_le(self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                <$t>::from_le_bytes(bytes.try_into().expect("read_le length"))
+            }
+
+            fn exp(self) -> Self {
+                <$t>::exp(self)
+            }
+
+            fn ln(self) -> Self {
+                <$t>::ln(self)
+            }
+
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            fn tanh(self) -> Self {
+                <$t>::tanh(self)
+            }
+
+            fn powi(self, n: i32) -> Self {
+                <$t>::powi(self, n)
+            }
+
+            fn max(self, other: Self) -> Self {
+                <$t>::max(self, other)
+            }
+
+            fn min(self, other: Self) -> Self {
+                <$t>::min(self, other)
+            }
+
+            fn is_finite(self) -> bool {
+                <$t>::is_finite(self)
+            }
+
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            fn mul_add_slice(cpu: Cpu, dst: &mut [Self], scale: Self, src: &[Self]) {
+                crate::simd::$mul_add_slice(cpu, dst, scale, src);
+            }
+
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            fn dot_slice(cpu: Cpu, a: &[Self], b: &[Self]) -> Self {
+                crate::simd::$dot_slice(cpu, a, b)
+            }
+        }
+    };
+}
+
+impl_float!(f32, mul_add_slice_f32, dot_slice_f32);
+impl_float!(f64, mul_add_slice_f64, dot_slice_f64);
@@ -0,0 +1,240 @@
+//! Building blocks of a `Sequential` model.
+
+use crate::activation::Activation;
+use crate::float::Float;
+use crate::neural_network::Matrix;
+use crate::reductions::Reduce;
+use crate::regularization::Regularization;
+use crate::sparse::CsrMatrix;
+
+/// A trainable matrix together with the gradient accumulated for it.
+pub struct Parameter<'a, T> {
+    pub value: &'a mut Matrix<T>,
+    pub grad: &'a mut Matrix<T>,
+    /// Whether weight decay applies, true for weights and false for biases.
+    pub decay: bool,
+}
+
+/// One step of a `Sequential` model, mapping a batch of `input_size` wide
+/// rows to a batch of `output_size` wide rows.
+pub trait Layer<T: Float>: std::fmt::Debug + Send + Sync {
+    fn input_size(&self) -> usize;
+
+    fn output_size(&self) -> usize;
+
+    /// Writes the output for `input` into `output`, keeping whatever
+    /// `backward` needs.
+    fn forward(&mut self, input: &Matrix<T>, output: &mut Matrix<T>);
+
+    /// `forward` for a batch of sparse rows, densified unless the layer can
+    /// skip the zeros.
+    fn forward_sparse(&mut self, input: &CsrMatrix<T>, output: &mut Matrix<T>) {
+        self.forward(&input.to_dense(), output);
+    }
+
+    /// Runs after the `forward` that produced `output` from `input`.
+    ///
+    /// `grad` is the cost gradient with respect to `output` and may be
+    /// overwritten, the parameter gradients are added to `gradients` and the
+    /// gradient with respect to `input` is written into `input_grad`.
+    fn backward(
+        &mut self,
+        input: &Matrix<T>,
+        output: &Matrix<T>,
+        grad: &mut Matrix<T>,
+        input_grad: &mut Matrix<T>,
+    );
+
+    fn parameters(&self) -> Vec<&Matrix<T>>;
+
+    /// Same order as `parameters`.
+    fn gradients(&self) -> Vec<&Matrix<T>>;
+
+    fn parameters_mut(&mut self) -> Vec<Parameter<'_, T>>;
+
+    /// Regularization penalty added to the cost.
+    fn penalty(&self) -> T {
+        T::ZERO
+    }
+
+    /// Adds the gradient of `penalty` to the parameter gradients.
+    fn add_penalty_gradient(&mut self) {}
+
+    fn name(&self) -> String;
+}
+
+/// Fully connected layer, `activation(input · w + b)`.
+#[derive(Debug)]
+pub struct Dense<T = f32> {
+    w: Matrix<T>,
+    b: Matrix<T>,
+    grad_w: Matrix<T>,
+    grad_b: Matrix<T>,
+    /// Pre-activations of the last `forward`, one row per sample.
+    z: Matrix<T>,
+    /// `inputᵀ · delta` of the last `backward`, before it is added to `grad_w`.
+    dw: Matrix<T>,
+    activation: Box<dyn Activation<T>>,
+    /// Applies to the weights only, not the biases.
+    regularization: Regularization,
+}
+
+impl<T: Float> Dense<T> {
+    /// Zero weights and biases, see `Sequential::rand`.
+    pub fn new(
+        inputs: usize,
+        outputs: usize,
+        activation: impl Activation<T> + 'static,
+    ) -> Dense<T> {
+        assert!(inputs > 0 && outputs > 0);
+
+        Dense {
+            w: Matrix::new(inputs, outputs),
+            b: Matrix::new(1, outputs),
+            grad_w: Matrix::new(inputs, outputs),
+            grad_b: Matrix::new(1, outputs),
+            z: Matrix::new(1, outputs),
+            dw: Matrix::new(inputs, outputs),
+            activation: Box::new(activation),
+            regularization: Regularization::default(),
+        }
+    }
+
+    pub fn with_regularization(mut self, regularization: Regularization) -> Dense<T> {
+        self.regularization = regularization;
+        self
+    }
+
+    pub fn set_regularization(&mut self, regularization: Regularization) {
+        self.regularization = regularization;
+    }
+
+    pub fn set_activation(&mut self, activation: impl Activation<T> + 'static) {
+        self.activation = Box::new(activation);
+    }
+
+    pub fn activation(&self) -> &dyn Activation<T> {
+        self.activation.as_ref()
+    }
+
+    pub fn w(&self) -> &Matrix<T> {
+        &self.w
+    }
+
+    pub fn b(&self) -> &Matrix<T> {
+        &self.b
+    }
+
+    /// Adds the biases to `z` and applies the activation.
+    fn activate(&mut self, output: &mut Matrix<T>) {
+        self.z.add(&self.b);
+        self.activation.apply(&self.z, output);
+    }
+}
+
+impl<T: Float> Layer<T> for Dense<T> {
+    fn input_size(&self) -> usize {
+        self.w.rows
+    }
+
+    fn output_size(&self) -> usize {
+        self.w.columns
+    }
+
+    fn forward(&mut self, input: &Matrix<T>, output: &mut Matrix<T>) {
+        self.z.resize_rows(input.rows);
+        self.z.dot_into(input, &self.w);
+        self.activate(output);
+    }
+
+    fn forward_sparse(&mut self, input: &CsrMatrix<T>, output: &mut Matrix<T>) {
+        self.z.resize_rows(input.rows);
+        self.z.sparse_dot_into(input, &self.w);
+        self.activate(output);
+    }
+
+    fn backward(
+        &mut self,
+        input: &Matrix<T>,
+        output: &Matrix<T>,
+        grad: &mut Matrix<T>,
+        input_grad: &mut Matrix<T>,
+    ) {
+        // turn grad from the activation gradient into the pre-activation delta
+        self.activation.backward(&self.z, output, grad);
+
+        self.grad_b.add(&grad.column_sums());
+        self.dw.transposed_dot_into(input, grad);
+        self.grad_w.add(&self.dw);
+
+        input_grad.dot_transposed_into(grad, &self.w);
+    }
+
+    fn parameters(&self) -> Vec<&Matrix<T>> {
+        vec![&self.w, &self.b]
+    }
+
+    fn gradients(&self) -> Vec<&Matrix<T>> {
+        vec![&self.grad_w, &self.grad_b]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<Parameter<'_, T>> {
+        vec![
+            Parameter {
+                value: &mut self.w,
+                grad: &mut self.grad_w,
+                decay: true,
+            },
+            Parameter {
+                value: &mut self.b,
+                grad: &mut self.grad_b,
+                decay: false,
+            },
+        ]
+    }
+
+    fn penalty(&self) -> T {
+        self.regularization.penalty(&self.w)
+    }
+
+    fn add_penalty_gradient(&mut self) {
+        if !self.regularization.is_none() {
+            self.regularization.add_gradient(&self.w, &mut self.grad_w);
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("dense {}", self.activation.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activation::Builtin;
+
+    #[test]
+    fn test_dense_forward_backward() {
+        let mut dense: Dense<f64> = Dense::new(2, 2, Builtin::Linear);
+        for (i, p) in dense.parameters_mut().into_iter().enumerate() {
+            p.value.map_inplace(|_| i as f64 + 1.);
+        }
+
+        let input = Matrix::from_rows([[1., -2.]]);
+        let mut output = Matrix::new(1, 2);
+        dense.forward(&input, &mut output);
+        // 1 * 1 + -2 * 1 + 2
+        assert_eq!(output.data, vec![1., 1.]);
+
+        let mut grad = Matrix::from_rows([[1., 0.5]]);
+        let mut input_grad = Matrix::new(1, 2);
+        dense.backward(&input, &output, &mut grad, &mut input_grad);
+        dense.backward(&input, &output, &mut grad, &mut input_grad);
+
+        assert_eq!(input_grad.data, vec![1.5, 1.5]);
+        let grads = dense.gradients();
+        assert_eq!(grads[0].data, vec![2., 1., -4., -2.]);
+        assert_eq!(grads[1].data, vec![2., 1.]);
+        assert_eq!(dense.name(), "dense linear");
+    }
+}
@@ -0,0 +1,53 @@
+//! Matrices, layers and training loops behind the nn demos of the app.
+
+#[allow(dead_code)]
+pub mod activation;
+#[allow(dead_code)]
+pub mod constructors;
+#[allow(dead_code)]
+pub mod data;
+#[allow(dead_code)]
+pub mod display;
+#[allow(dead_code)]
+pub mod encoding;
+#[allow(dead_code)]
+pub mod error;
+#[allow(dead_code)]
+pub mod float;
+#[allow(dead_code)]
+pub mod layer;
+#[allow(dead_code)]
+pub mod linalg;
+#[allow(dead_code)]
+pub mod loss;
+#[allow(dead_code)]
+pub mod matmul;
+#[allow(dead_code)]
+pub mod matrix_ops;
+#[allow(dead_code)]
+pub mod matrix_view;
+#[allow(dead_code)]
+pub mod neural_network;
+#[allow(dead_code)]
+pub mod optimizer;
+#[cfg(feature = "parallel")]
+#[allow(dead_code)]
+pub mod parallel;
+#[allow(dead_code)]
+pub mod random;
+#[allow(dead_code)]
+pub mod reductions;
+#[allow(dead_code)]
+pub mod regularization;
+#[allow(dead_code)]
+pub mod schedule;
+#[allow(dead_code)]
+pub mod sequential;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd;
+#[allow(dead_code)]
+pub mod sparse;
+#[allow(dead_code)]
+pub mod tensor;
+#[allow(dead_code)]
+pub mod training;
@@ -0,0 +1,322 @@
+//! Dense decompositions: LU with partial pivoting and Householder QR.
+
+use crate::error::{LinalgError, ShapeError};
+use crate::float::Float;
+use crate::matrix_view::AsMatrixView;
+use crate::neural_network::Matrix;
+
+/// Pivots at or below this are treated as zero, scaled to the size and
+/// magnitude of `a` so the check does not depend on units.
+fn tolerance<T: Float>(a: &Matrix<T>) -> T {
+    let max_abs = a.data.iter().fold(T::ZERO, |max, x| max.max(x.abs()));
+    T::EPSILON * T::from_usize(a.rows.max(a.columns)) * max_abs
+}
+
+fn is_zero_pivot<T: Float>(pivot: T, tolerance: T) -> bool {
+    pivot.abs() <= tolerance || !pivot.is_finite()
+}
+
+/// `P · A = L · U`, with the unit lower `L` and upper `U` packed into one matrix.
+#[derive(Debug, Clone)]
+pub struct Lu<T = f32> {
+    lu: Matrix<T>,
+    /// Row `i` of `P · A` is row `perm[i]` of `A`.
+    perm: Vec<usize>,
+    /// Determinant of `P`.
+    sign: T,
+}
+
+impl<T: Float> Lu<T> {
+    pub fn try_new(a: &impl AsMatrixView<T>) -> Result<Lu<T>, LinalgError> {
+        let mut lu = a.view().to_matrix();
+        let n = lu.rows;
+        ShapeError::check("lu", (n, n), lu.shape())?;
+
+        let tolerance = tolerance(&lu);
+        let mut perm: Vec<usize> = (0..n).collect();
+        let mut sign = T::ONE;
+
+        for k in 0..n {
+            let mut pivot = k;
+            for row in k + 1..n {
+                if lu[(row, k)].abs() > lu[(pivot, k)].abs() {
+                    pivot = row;
+                }
+            }
+            if is_zero_pivot(lu[(pivot, k)], tolerance) {
+                return Err(LinalgError::Singular { column: k });
+            }
+
+            if pivot != k {
+                for col in 0..n {
+                    lu.data.swap(k * n + col, pivot * n + col);
+                }
+                perm.swap(k, pivot);
+                sign = -sign;
+            }
+
+            for row in k + 1..n {
+                let factor = lu[(row, k)] / lu[(k, k)];
+                lu[(row, k)] = factor;
+                for col in k + 1..n {
+                    let value = lu[(k, col)];
+                    lu[(row, col)] -= factor * value;
+                }
+            }
+        }
+
+        Ok(Lu { lu, perm, sign })
+    }
+
+    /// `x` with `A · x = b`, `b` may hold several right-hand sides as columns.
+    pub fn try_solve(&self, b: &impl AsMatrixView<T>) -> Result<Matrix<T>, ShapeError> {
+        let b = b.view();
+        let n = self.lu.rows;
+        ShapeError::check("solve", (n, b.columns), b.shape())?;
+
+        let mut x = Matrix::from_fn(n, b.columns, |row, col| b.get(self.perm[row], col));
+        for col in 0..x.columns {
+            // L · y = P · b, L has a unit diagonal
+            for row in 0..n {
+                let mut sum = x[(row, col)];
+                for k in 0..row {
+                    sum -= self.lu[(row, k)] * x[(k, col)];
+                }
+                x[(row, col)] = sum;
+            }
+            // U · x = y
+            for row in (0..n).rev() {
+                let mut sum = x[(row, col)];
+                for k in row + 1..n {
+                    sum -= self.lu[(row, k)] * x[(k, col)];
+                }
+                x[(row, col)] = sum / self.lu[(row, row)];
+            }
+        }
+
+        Ok(x)
+    }
+
+    pub fn solve(&self, b: &impl AsMatrixView<T>) -> Matrix<T> {
+        self.try_solve(b).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn inverse(&self) -> Matrix<T> {
+        self.solve(&Matrix::identity(self.lu.rows))
+    }
+
+    pub fn det(&self) -> T {
+        (0..self.lu.rows).fold(self.sign, |det, k| det * self.lu[(k, k)])
+    }
+}
+
+/// `A = Q · R` from Householder reflections, for `A` with at least as many
+/// rows as columns.
+#[derive(Debug, Clone)]
+pub struct Qr<T = f32> {
+    /// `R` in the upper triangle, zeros below.
+    r: Matrix<T>,
+    /// Unit vector of reflection `k`, acting on rows `k..`.
+    reflectors: Vec<Vec<T>>,
+    tolerance: T,
+}
+
+impl<T: Float> Qr<T> {
+    pub fn try_new(a: &impl AsMatrixView<T>) -> Result<Qr<T>, ShapeError> {
+        let mut r = a.view().to_matrix();
+        let (m, n) = r.shape();
+        if m < n {
+            return Err(ShapeError {
+                op: "qr",
+                expected: (n, n),
+                actual: (m, n),
+            });
+        }
+
+        let tolerance = tolerance(&r);
+        let mut reflectors = Vec::with_capacity(n);
+
+        for k in 0..n {
+            let mut v: Vec<T> = (k..m).map(|row| r[(row, k)]).collect();
+            let norm = v.iter().map(|&x| x * x).sum::<T>().sqrt();
+            // reflect onto -sign(x0) * |x| e0 so v0 never cancels
+            let alpha = if v[0] > T::ZERO { -norm } else { norm };
+            v[0] -= alpha;
+
+            let v_norm = v.iter().map(|&x| x * x).sum::<T>().sqrt();
+            if v_norm > T::ZERO {
+                for x in v.iter_mut() {
+                    *x /= v_norm;
+                }
+                reflect(&mut r, k, &v, k);
+            }
+            reflectors.push(v);
+        }
+
+        Ok(Qr {
+            r,
+            reflectors,
+            tolerance,
+        })
+    }
+
+    pub fn new(a: &impl AsMatrixView<T>) -> Qr<T> {
+        Qr::try_new(a).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Square upper triangular `R`.
+    pub fn r(&self) -> Matrix<T> {
+        let n = self.r.columns;
+        self.r.block(0, 0, n, n).to_matrix()
+    }
+
+    /// `Q` with orthonormal columns, the same shape as `A`.
+    pub fn q(&self) -> Matrix<T> {
+        let (m, n) = self.r.shape();
+        let mut q = Matrix::from_fn(m, n, |row, col| if row == col { T::ONE } else { T::ZERO });
+        for (k, v) in self.reflectors.iter().enumerate().rev() {
+            reflect(&mut q, k, v, 0);
+        }
+        q
+    }
+
+    /// `x` minimizing `|A · x - b|`, errors when the columns of `A` are
+    /// linearly dependent.
+    pub fn try_least_squares(&self, b: &impl AsMatrixView<T>) -> Result<Matrix<T>, LinalgError> {
+        let (m, n) = self.r.shape();
+        let mut y = b.view().to_matrix();
+        ShapeError::check("least_squares", (m, y.columns), y.shape())?;
+
+        // y = Qᵀ · b
+        for (k, v) in self.reflectors.iter().enumerate() {
+            reflect(&mut y, k, v, 0);
+        }
+
+        let mut x = Matrix::new(n, y.columns);
+        for row in (0..n).rev() {
+            let pivot = self.r[(row, row)];
+            if is_zero_pivot(pivot, self.tolerance) {
+                return Err(LinalgError::Singular { column: row });
+            }
+
+            for col in 0..y.columns {
+                let mut sum = y[(row, col)];
+                for k in row + 1..n {
+                    sum -= self.r[(row, k)] * x[(k, col)];
+                }
+                x[(row, col)] = sum / pivot;
+            }
+        }
+
+        Ok(x)
+    }
+
+    pub fn least_squares(&self, b: &impl AsMatrixView<T>) -> Matrix<T> {
+        self.try_least_squares(b)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+/// Applies `I - 2 v vᵀ` to rows `k..` of `m`, columns `first_col..`.
+fn reflect<T: Float>(m: &mut Matrix<T>, k: usize, v: &[T], first_col: usize) {
+    let two = T::ONE + T::ONE;
+    for col in first_col..m.columns {
+        let dot: T = v
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| x * m[(k + i, col)])
+            .sum();
+        for (i, &x) in v.iter().enumerate() {
+            m[(k + i, col)] -= two * dot * x;
+        }
+    }
+}
+
+impl<T: Float> Matrix<T> {
+    /// `x` with `self · x = b` for square `self`.
+    pub fn try_solve(&self, b: &impl AsMatrixView<T>) -> Result<Matrix<T>, LinalgError> {
+        Ok(Lu::try_new(self)?.try_solve(b)?)
+    }
+
+    pub fn solve(&self, b: &impl AsMatrixView<T>) -> Matrix<T> {
+        self.try_solve(b).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_inverse(&self) -> Result<Matrix<T>, LinalgError> {
+        Ok(Lu::try_new(self)?.inverse())
+    }
+
+    pub fn inverse(&self) -> Matrix<T> {
+        self.try_inverse().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Determinant of a square matrix, 0 when it is singular.
+    pub fn try_det(&self) -> Result<T, ShapeError> {
+        match Lu::try_new(self) {
+            Ok(lu) => Ok(lu.det()),
+            Err(LinalgError::Singular { .. }) => Ok(T::ZERO),
+            Err(LinalgError::Shape(err)) => Err(err),
+        }
+    }
+
+    pub fn det(&self) -> T {
+        self.try_det().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// `x` minimizing `|self · x - b|`, see `Qr::try_least_squares`.
+    pub fn try_least_squares(&self, b: &impl AsMatrixView<T>) -> Result<Matrix<T>, LinalgError> {
+        Qr::try_new(self)?.try_least_squares(b)
+    }
+
+    pub fn least_squares(&self, b: &impl AsMatrixView<T>) -> Matrix<T> {
+        self.try_least_squares(b)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Matrix<f64>, b: &Matrix<f64>) {
+        assert_eq!(a.shape(), b.shape());
+        for (x, y) in a.data.iter().zip(&b.data) {
+            assert!((x - y).abs() < 1e-9, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_lu_and_qr() {
+        let a: Matrix<f64> = Matrix::from_rows([[0., 2., 1.], [1., 1., 0.], [3., 0., 4.]]);
+        let x = Matrix::from_rows([[1., -1.], [2., 0.], [3., 5.]]);
+        let b = &a * &x;
+
+        assert_close(&a.solve(&b), &x);
+        assert_close(&(&a * &a.inverse()), &Matrix::identity(3));
+        assert!((a.det() - -11.).abs() < 1e-12);
+
+        let singular: Matrix<f64> = Matrix::from_rows([[1., 2.], [2., 4.]]);
+        assert_eq!(
+            singular.try_solve(&Matrix::new(2, 1)).unwrap_err(),
+            LinalgError::Singular { column: 1 }
+        );
+        assert_eq!(singular.det(), 0.);
+        assert!(Matrix::<f64>::new(2, 3).try_det().is_err());
+
+        // y = 2x + 1 through noisy-free points, columns are [x, 1]
+        let design: Matrix<f64> = Matrix::from_fn(5, 2, |r, c| if c == 0 { r as f64 } else { 1. });
+        let y = Matrix::from_fn(5, 1, |r, _| 2. * r as f64 + 1.);
+        assert_close(&design.least_squares(&y), &Matrix::from_rows([[2.], [1.]]));
+
+        let qr = Qr::new(&design);
+        assert_close(&(&qr.q() * &qr.r()), &design);
+        let q = qr.q();
+        assert_close(&(&q.t().to_matrix() * &q), &Matrix::identity(2));
+
+        let dependent: Matrix<f64> = Matrix::from_fn(4, 2, |r, _| r as f64);
+        assert!(matches!(
+            dependent.try_least_squares(&Matrix::new(4, 1)),
+            Err(LinalgError::Singular { column: 1 })
+        ));
+    }
+}
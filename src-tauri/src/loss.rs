@@ -0,0 +1,241 @@
+//! Costs comparing a batch of outputs to the expected outputs, one sample per row.
+
+use crate::float::Float;
+use crate::matrix_view::{AsMatrixView, MatrixView};
+use crate::neural_network::Matrix;
+use crate::reductions::Reduce;
+
+pub trait Loss<T: Float>: std::fmt::Debug + Send + Sync {
+    /// Mean over the rows of the per-sample loss.
+    fn loss(&self, output: &Matrix<T>, truth: &MatrixView<T>) -> T;
+
+    /// Writes the gradient of `loss` with respect to `output` into `grad`.
+    fn gradient(&self, output: &Matrix<T>, truth: &MatrixView<T>, grad: &mut Matrix<T>);
+
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Sum of `f(output, truth)` over every element, divided by the rows.
+fn mean_of<T: Float>(output: &Matrix<T>, truth: &MatrixView<T>, f: impl Fn(T, T) -> T) -> T {
+    let sum = output
+        .view()
+        .iter()
+        .zip(truth.iter())
+        .map(|(a, y)| f(a, y))
+        .sum::<T>();
+    sum / T::from_usize(output.rows)
+}
+
+/// Writes `f(output, truth) / rows` for every element into `grad`.
+fn gradient_of<T: Float>(
+    output: &Matrix<T>,
+    truth: &MatrixView<T>,
+    grad: &mut Matrix<T>,
+    f: impl Fn(T, T) -> T + Sync,
+) {
+    let n = T::from_usize(output.rows);
+    grad.copy_from(output);
+    grad.zip_with_inplace(truth, |a, y| f(a, y) / n);
+}
+
+/// Squared error summed over the columns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mse;
+
+impl<T: Float> Loss<T> for Mse {
+    fn loss(&self, output: &Matrix<T>, truth: &MatrixView<T>) -> T {
+        mean_of(output, truth, |a, y| (a - y) * (a - y))
+    }
+
+    fn gradient(&self, output: &Matrix<T>, truth: &MatrixView<T>, grad: &mut Matrix<T>) {
+        let two = T::from_f64(2.);
+        gradient_of(output, truth, grad, |a, y| two * (a - y));
+    }
+}
+
+/// Absolute error summed over the columns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mae;
+
+impl<T: Float> Loss<T> for Mae {
+    fn loss(&self, output: &Matrix<T>, truth: &MatrixView<T>) -> T {
+        mean_of(output, truth, |a, y| (a - y).abs())
+    }
+
+    fn gradient(&self, output: &Matrix<T>, truth: &MatrixView<T>, grad: &mut Matrix<T>) {
+        gradient_of(output, truth, grad, |a, y| (a - y).sign());
+    }
+}
+
+/// Squared error within `delta` of the truth, absolute error beyond it.
+#[derive(Debug, Clone, Copy)]
+pub struct Huber {
+    pub delta: f64,
+}
+
+impl<T: Float> Loss<T> for Huber {
+    fn loss(&self, output: &Matrix<T>, truth: &MatrixView<T>) -> T {
+        let delta = T::from_f64(self.delta);
+        let half = T::from_f64(0.5);
+        mean_of(output, truth, |a, y| {
+            let d = (a - y).abs();
+            if d <= delta {
+                half * d * d
+            } else {
+                delta * (d - half * delta)
+            }
+        })
+    }
+
+    fn gradient(&self, output: &Matrix<T>, truth: &MatrixView<T>, grad: &mut Matrix<T>) {
+        let delta = T::from_f64(self.delta);
+        gradient_of(output, truth, grad, |a, y| {
+            let d = a - y;
+            if d.abs() <= delta {
+                d
+            } else {
+                delta * d.sign()
+            }
+        });
+    }
+}
+
+/// Cross-entropy of independent probabilities, such as sigmoid outputs.
+/// Outputs are clamped away from 0 and 1 so the loss stays finite.
+///
+/// Past the clamp the loss is flat, but the gradient keeps its value at the
+/// clamp edge rather than dropping to 0, so an output saturated on the wrong
+/// side is still pushed back.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinaryCrossEntropy;
+
+fn clamp_probability<T: Float>(a: T) -> T {
+    let eps = T::from_f64(1e-7);
+    a.max(eps).min(T::ONE - eps)
+}
+
+impl<T: Float> Loss<T> for BinaryCrossEntropy {
+    fn loss(&self, output: &Matrix<T>, truth: &MatrixView<T>) -> T {
+        mean_of(output, truth, |a, y| {
+            let a = clamp_probability(a);
+            -(y * a.ln() + (T::ONE - y) * (T::ONE - a).ln())
+        })
+    }
+
+    fn gradient(&self, output: &Matrix<T>, truth: &MatrixView<T>, grad: &mut Matrix<T>) {
+        gradient_of(output, truth, grad, |a, y| {
+            let a = clamp_probability(a);
+            (a - y) / (a * (T::ONE - a))
+        });
+    }
+}
+
+/// Softmax over each row followed by categorical cross-entropy, computed
+/// together from the logits so neither step can overflow.
+///
+/// The model's last layer should be `Linear`, its outputs are the logits.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SoftmaxCrossEntropy;
+
+/// `(max, ln(sum(exp(row - max))))` of a row of logits.
+fn log_sum_exp<T: Float>(row: &MatrixView<T>) -> (T, T) {
+    let max = row.max().unwrap_or(T::ZERO);
+    let sum = row.iter().map(|z| (z - max).exp()).sum::<T>();
+    (max, sum.ln())
+}
+
+impl<T: Float> Loss<T> for SoftmaxCrossEntropy {
+    fn loss(&self, output: &Matrix<T>, truth: &MatrixView<T>) -> T {
+        let mut loss = T::ZERO;
+        for row in 0..output.rows {
+            let logits = output.row(row);
+            let (max, lse) = log_sum_exp(&logits);
+            for (z, y) in logits.iter().zip(truth.row(row).iter()) {
+                // -y * ln(softmax(z))
+                loss += y * (lse - (z - max));
+            }
+        }
+        loss / T::from_usize(output.rows)
+    }
+
+    fn gradient(&self, output: &Matrix<T>, truth: &MatrixView<T>, grad: &mut Matrix<T>) {
+        let n = T::from_usize(output.rows);
+        for row in 0..output.rows {
+            let logits = output.row(row);
+            let (max, lse) = log_sum_exp(&logits);
+            let total = truth.row(row).iter().sum::<T>();
+            for col in 0..output.columns {
+                let softmax = (logits.get(0, col) - max - lse).exp();
+                grad[(row, col)] = (softmax * total - truth.get(row, col)) / n;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loss_gradients() {
+        let output: Matrix<f64> = Matrix::from_rows([[0.2, 0.9, 0.4], [0.7, 0.1, 0.55]]);
+        let truth = Matrix::from_rows([[0., 1., 0.], [1., 0., 0.]]);
+        let truth = truth.view();
+
+        let losses: [Box<dyn Loss<f64>>; 6] = [
+            Box::new(Mse),
+            Box::new(Mae),
+            Box::new(Huber { delta: 0.5 }),
+            Box::new(Huber { delta: 0.1 }),
+            Box::new(BinaryCrossEntropy),
+            Box::new(SoftmaxCrossEntropy),
+        ];
+        for loss in losses.iter() {
+            let mut grad = Matrix::new(2, 3);
+            loss.gradient(&output, &truth, &mut grad);
+
+            let eps = 1e-6;
+            for i in 0..output.data.len() {
+                let mut shifted = output.clone();
+                shifted.data[i] += eps;
+                let plus = loss.loss(&shifted, &truth);
+                shifted.data[i] -= 2. * eps;
+                let minus = loss.loss(&shifted, &truth);
+
+                let expected = (plus - minus) / (2. * eps);
+                assert!(
+                    (expected - grad.data[i]).abs() < 1e-6,
+                    "{}: {} != {}",
+                    loss.name(),
+                    expected,
+                    grad.data[i]
+                );
+            }
+        }
+
+        // logits far beyond what exp can hold
+        let logits: Matrix<f64> = Matrix::from_rows([[1000., 0.], [-1000., 1000.]]);
+        let onehot = Matrix::from_rows([[1., 0.], [1., 0.]]);
+        let loss = SoftmaxCrossEntropy.loss(&logits, &onehot.view());
+        assert_eq!(loss, 1000.);
+        let mut grad = Matrix::new(2, 2);
+        SoftmaxCrossEntropy.gradient(&logits, &onehot.view(), &mut grad);
+        assert_eq!(grad.data, vec![0., 0., -0.5, 0.5]);
+
+        // saturated outputs cost and train like the clamp edge
+        let truth = Matrix::from_rows([[1., 1.]]);
+        let saturated: Matrix<f64> = Matrix::from_rows([[0., 1.]]);
+        let edge = Matrix::from_rows([[1e-7, 1. - 1e-7]]);
+        let loss = BinaryCrossEntropy.loss(&saturated, &truth.view());
+        assert!(loss.is_finite() && loss == BinaryCrossEntropy.loss(&edge, &truth.view()));
+        let (mut grad, mut edge_grad) = (Matrix::new(1, 2), Matrix::new(1, 2));
+        BinaryCrossEntropy.gradient(&saturated, &truth.view(), &mut grad);
+        BinaryCrossEntropy.gradient(&edge, &truth.view(), &mut edge_grad);
+        assert_eq!(grad.data, edge_grad.data);
+        assert!(grad.data[0] < -1e6);
+
+        assert_eq!(Mse.loss(&output, &output.view()), 0.);
+    }
+}
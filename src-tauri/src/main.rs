@@ -1,10 +1,6 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-#[allow(dead_code)]
-mod data;
-#[allow(dead_code)]
-mod neural_network;
 mod nn1;
 mod nn2;
 mod nn3;
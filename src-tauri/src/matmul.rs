@@ -0,0 +1,157 @@
+use crate::float::{Cpu, Float};
+use crate::matrix_view::{MatrixView, MatrixViewMut};
+#[cfg(feature = "parallel")]
+use crate::parallel::{self, Threading};
+
+// Tile sizes for the blocked kernel, a BLOCK_K x BLOCK_COLS tile of `b`
+// (128 KiB of f32) stays in L2 while every row of `a` streams over it
+const BLOCK_K: usize = 128;
+const BLOCK_COLS: usize = 256;
+
+/// Writes `a · b` into `c`, shapes must already be checked.
+///
+/// Picks a kernel from the operand layouts:
+/// - rows of `b` and `c` contiguous, blocked `c[i, ..] += a[i, k] * b[k, ..]`
+/// - rows of `a` and columns of `b` contiguous (`b` is a transposed view), dot products
+/// - anything else falls back to the strided triple loop
+pub(crate) fn gemm<T: Float>(c: &mut MatrixViewMut<T>, a: &MatrixView<T>, b: &MatrixView<T>) {
+    if c.rows == 0 || c.columns == 0 {
+        return;
+    }
+
+    #[cfg(feature = "parallel")]
+    gemm_threaded(c, a, b, Threading::current());
+
+    #[cfg(not(feature = "parallel"))]
+    gemm_serial(c, a, b, Cpu::detect());
+}
+
+/// `gemm` split over row blocks of `c` when `threading` says the work is
+/// worth it.
+#[cfg(feature = "parallel")]
+pub(crate) fn gemm_threaded<T: Float>(
+    c: &mut MatrixViewMut<T>,
+    a: &MatrixView<T>,
+    b: &MatrixView<T>,
+    threading: Threading,
+) {
+    let cpu = Cpu::detect();
+    if c.rows_disjoint() && threading.worth_splitting(c.rows * c.columns * a.columns) {
+        parallel::for_row_blocks(threading.threads(), c.reborrow(), |row, mut block| {
+            let a = a.block(row, 0, block.rows, a.columns);
+            gemm_serial(&mut block, &a, b, cpu)
+        });
+    } else {
+        gemm_serial(c, a, b, cpu);
+    }
+}
+
+pub(crate) fn gemm_serial<T: Float>(
+    c: &mut MatrixViewMut<T>,
+    a: &MatrixView<T>,
+    b: &MatrixView<T>,
+    cpu: Cpu,
+) {
+    if b.col_stride == 1 && c.col_stride == 1 {
+        gemm_blocked(c, a, b, cpu);
+    } else if a.col_stride == 1 && b.row_stride == 1 {
+        gemm_dot(c, a, b, cpu);
+    } else {
+        gemm_naive(c, a, b);
+    }
+}
+
+fn gemm_blocked<T: Float>(
+    c: &mut MatrixViewMut<T>,
+    a: &MatrixView<T>,
+    b: &MatrixView<T>,
+    cpu: Cpu,
+) {
+    let (rows, inner, columns) = (c.rows, a.columns, c.columns);
+
+    for row in 0..rows {
+        let start = row * c.row_stride;
+        c.data[start..start + columns].fill(T::ZERO);
+    }
+
+    for k0 in (0..inner).step_by(BLOCK_K) {
+        let k1 = (k0 + BLOCK_K).min(inner);
+
+        for j0 in (0..columns).step_by(BLOCK_COLS) {
+            let j1 = (j0 + BLOCK_COLS).min(columns);
+
+            for row in 0..rows {
+                let start = row * c.row_stride;
+                let c_row = &mut c.data[start + j0..start + j1];
+
+                for k in k0..k1 {
+                    let scale = a.data[row * a.row_stride + k * a.col_stride];
+                    let b_row = &b.data[k * b.row_stride + j0..k * b.row_stride + j1];
+                    T::mul_add_slice(cpu, c_row, scale, b_row);
+                }
+            }
+        }
+    }
+}
+
+fn gemm_dot<T: Float>(c: &mut MatrixViewMut<T>, a: &MatrixView<T>, b: &MatrixView<T>, cpu: Cpu) {
+    let inner = a.columns;
+
+    for row in 0..c.rows {
+        let a_row = &a.data[row * a.row_stride..row * a.row_stride + inner];
+        for col in 0..c.columns {
+            let b_col = &b.data[col * b.col_stride..col * b.col_stride + inner];
+            c.data[row * c.row_stride + col * c.col_stride] = T::dot_slice(cpu, a_row, b_col);
+        }
+    }
+}
+
+fn gemm_naive<T: Float>(c: &mut MatrixViewMut<T>, a: &MatrixView<T>, b: &MatrixView<T>) {
+    for row in 0..c.rows {
+        for col in 0..c.columns {
+            let mut sum = T::ZERO;
+            for k in 0..a.columns {
+                sum += a.data[row * a.row_stride + k * a.col_stride]
+                    * b.data[k * b.row_stride + col * b.col_stride];
+            }
+            c.data[row * c.row_stride + col * c.col_stride] = sum;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix_view::AsMatrixView;
+    use crate::neural_network::Matrix;
+
+    fn sample(rows: usize, columns: usize) -> Matrix<f32> {
+        let mut m = Matrix::new(rows, columns);
+        for (i, value) in m.data.iter_mut().enumerate() {
+            *value = ((i * 7 + 3) % 11) as f32 / 11. - 0.5;
+        }
+        m
+    }
+
+    #[test]
+    fn test_gemm_kernels_agree() {
+        // odd sizes so every block edge and simd tail is hit
+        let a = sample(37, 301);
+        let b = sample(301, 263);
+        let bt = b.t().to_matrix();
+
+        let mut naive = Matrix::new(37, 263);
+        gemm_naive(&mut naive.view_mut(), &a.view(), &b.view());
+
+        let mut blocked = Matrix::new(37, 263);
+        gemm(&mut blocked.view_mut(), &a.view(), &b.view());
+
+        let mut dot = Matrix::new(37, 263);
+        gemm(&mut dot.view_mut(), &a.view(), &bt.t());
+
+        for i in 0..naive.data.len() {
+            assert!((naive.data[i] - blocked.data[i]).abs() < 1e-3);
+            assert!((naive.data[i] - dot.data[i]).abs() < 1e-3);
+        }
+    }
+}
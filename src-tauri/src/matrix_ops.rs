@@ -0,0 +1,208 @@
+use crate::float::Float;
+use crate::matrix_view::AsMatrixView;
+use crate::neural_network::Matrix;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
+
+impl<T: Float> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(row < self.rows);
+        assert!(col < self.columns);
+
+        &self.data[row * self.columns + col]
+    }
+}
+
+impl<T: Float> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(row < self.rows);
+        assert!(col < self.columns);
+
+        &mut self.data[row * self.columns + col]
+    }
+}
+
+// Elementwise operators, every combination of owned and borrowed operands.
+// The borrowed right hand side accepts any view so `&m.row(0)` works too.
+macro_rules! elementwise_op {
+    ($op:ident, $fn:ident, $assign_op:ident, $assign_fn:ident, $method:ident) => {
+        impl<T: Float, R: AsMatrixView<T>> $assign_op<&R> for Matrix<T> {
+            fn $assign_fn(&mut self, other: &R) {
+                Matrix::$method(self, other);
+            }
+        }
+
+        impl<T: Float> $assign_op<Matrix<T>> for Matrix<T> {
+            fn $assign_fn(&mut self, other: Matrix<T>) {
+                Matrix::$method(self, &other);
+            }
+        }
+
+        impl<T: Float, R: AsMatrixView<T>> $op<&R> for Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $fn(mut self, other: &R) -> Matrix<T> {
+                Matrix::$method(&mut self, other);
+                self
+            }
+        }
+
+        impl<T: Float> $op<Matrix<T>> for Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $fn(mut self, other: Matrix<T>) -> Matrix<T> {
+                Matrix::$method(&mut self, &other);
+                self
+            }
+        }
+
+        impl<T: Float, R: AsMatrixView<T>> $op<&R> for &Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $fn(self, other: &R) -> Matrix<T> {
+                let mut result = self.clone();
+                Matrix::$method(&mut result, other);
+                result
+            }
+        }
+
+        impl<T: Float> $op<Matrix<T>> for &Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $fn(self, other: Matrix<T>) -> Matrix<T> {
+                let mut result = self.clone();
+                Matrix::$method(&mut result, &other);
+                result
+            }
+        }
+    };
+}
+
+elementwise_op!(Add, add, AddAssign, add_assign, add);
+elementwise_op!(Sub, sub, SubAssign, sub_assign, sub);
+
+// Matrix product
+impl<T: Float, R: AsMatrixView<T>> Mul<&R> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, other: &R) -> Matrix<T> {
+        let mut result = Matrix::new(self.rows, other.view().columns);
+        result.dot_into(self, other);
+        result
+    }
+}
+
+impl<T: Float, R: AsMatrixView<T>> Mul<&R> for Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, other: &R) -> Matrix<T> {
+        &self * other
+    }
+}
+
+impl<T: Float> Mul<Matrix<T>> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, other: Matrix<T>) -> Matrix<T> {
+        self * &other
+    }
+}
+
+impl<T: Float> Mul<Matrix<T>> for Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, other: Matrix<T>) -> Matrix<T> {
+        &self * &other
+    }
+}
+
+// Scalar product
+impl<T: Float> MulAssign<T> for Matrix<T> {
+    fn mul_assign(&mut self, value: T) {
+        self.dotf(value);
+    }
+}
+
+// Spelled out per element type, a generic `Mul<T>` would overlap with the
+// matrix product impls above and `impl<T> Mul<Matrix<T>> for T` is not allowed
+macro_rules! scalar_mul {
+    ($t:ty) => {
+        impl Mul<$t> for Matrix<$t> {
+            type Output = Matrix<$t>;
+
+            fn mul(mut self, value: $t) -> Matrix<$t> {
+                self.dotf(value);
+                self
+            }
+        }
+
+        impl Mul<$t> for &Matrix<$t> {
+            type Output = Matrix<$t>;
+
+            fn mul(self, value: $t) -> Matrix<$t> {
+                self.clone() * value
+            }
+        }
+
+        impl Mul<Matrix<$t>> for $t {
+            type Output = Matrix<$t>;
+
+            fn mul(self, matrix: Matrix<$t>) -> Matrix<$t> {
+                matrix * self
+            }
+        }
+
+        impl Mul<&Matrix<$t>> for $t {
+            type Output = Matrix<$t>;
+
+            fn mul(self, matrix: &Matrix<$t>) -> Matrix<$t> {
+                matrix * self
+            }
+        }
+    };
+}
+
+scalar_mul!(f32);
+scalar_mul!(f64);
+
+impl<T: Float> Neg for Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn neg(mut self) -> Matrix<T> {
+        self.dotf(-T::ONE);
+        self
+    }
+}
+
+impl<T: Float> Neg for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn neg(self) -> Matrix<T> {
+        -self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_operators() {
+        let a: Matrix = Matrix::from_2d_vec(&vec![vec![1., 2.], vec![3., 4.]]);
+        let b = Matrix::from_2d_vec(&vec![vec![0., 1.], vec![1., 0.]]);
+
+        assert_eq!((&a + &b).data, vec![1., 3., 4., 4.]);
+        assert_eq!((&a - b.clone()).data, vec![1., 1., 2., 4.]);
+        assert_eq!((&a * &b).data, vec![2., 1., 4., 3.]);
+        assert_eq!((2. * &a).data, vec![2., 4., 6., 8.]);
+        assert_eq!((-a.clone()).data, vec![-1., -2., -3., -4.]);
+        assert_eq!((&a + &a.t()).data, vec![2., 5., 5., 8.]);
+
+        let mut c = a.clone();
+        c -= &a;
+        c[(1, 0)] = 5.;
+        assert_eq!(c.data, vec![0., 0., 5., 0.]);
+        assert_eq!(a[(1, 1)], 4.);
+    }
+}
@@ -0,0 +1,635 @@
+use crate::error::ShapeError;
+use crate::float::Float;
+use crate::matmul;
+use crate::neural_network::{sigmoid, Matrix};
+#[cfg(feature = "parallel")]
+use crate::parallel;
+
+/// Number of elements a strided view reaches into its backing slice.
+fn span(rows: usize, columns: usize, row_stride: usize, col_stride: usize) -> usize {
+    if rows == 0 || columns == 0 {
+        0
+    } else {
+        (rows - 1) * row_stride + (columns - 1) * col_stride + 1
+    }
+}
+
+/// Anything that can be read as a borrowed matrix.
+pub trait AsMatrixView<T: Float> {
+    fn view(&self) -> MatrixView<'_, T>;
+}
+
+impl<T: Float> AsMatrixView<T> for Matrix<T> {
+    fn view(&self) -> MatrixView<'_, T> {
+        MatrixView {
+            rows: self.rows,
+            columns: self.columns,
+            row_stride: self.columns,
+            col_stride: 1,
+            data: &self.data,
+        }
+    }
+}
+
+impl<T: Float> AsMatrixView<T> for MatrixView<'_, T> {
+    fn view(&self) -> MatrixView<'_, T> {
+        *self
+    }
+}
+
+impl<T: Float> AsMatrixView<T> for MatrixViewMut<'_, T> {
+    fn view(&self) -> MatrixView<'_, T> {
+        MatrixView {
+            rows: self.rows,
+            columns: self.columns,
+            row_stride: self.row_stride,
+            col_stride: self.col_stride,
+            data: self.data,
+        }
+    }
+}
+
+/// Borrowed, possibly strided, read-only window into matrix data.
+#[derive(Debug, Clone, Copy)]
+pub struct MatrixView<'a, T = f32> {
+    pub rows: usize,
+    pub columns: usize,
+    pub(crate) row_stride: usize,
+    pub(crate) col_stride: usize,
+    pub(crate) data: &'a [T],
+}
+
+impl<'a, T: Float> MatrixView<'a, T> {
+    /// Views a row-major slice as a `rows` x `columns` matrix.
+    pub fn new(data: &'a [T], rows: usize, columns: usize) -> MatrixView<'a, T> {
+        assert_eq!(data.len(), rows * columns);
+
+        MatrixView {
+            rows,
+            columns,
+            row_stride: columns,
+            col_stride: 1,
+            data,
+        }
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> T {
+        assert!(row < self.rows);
+        assert!(col < self.columns);
+
+        self.data[row * self.row_stride + col * self.col_stride]
+    }
+
+    pub fn row(&self, row: usize) -> MatrixView<'a, T> {
+        self.block(row, 0, 1, self.columns)
+    }
+
+    pub fn column(&self, col: usize) -> MatrixView<'a, T> {
+        self.block(0, col, self.rows, 1)
+    }
+
+    pub fn block(&self, row: usize, col: usize, rows: usize, columns: usize) -> MatrixView<'a, T> {
+        assert!(row + rows <= self.rows);
+        assert!(col + columns <= self.columns);
+
+        let start = (row * self.row_stride + col * self.col_stride).min(self.data.len());
+        let end = start + span(rows, columns, self.row_stride, self.col_stride);
+
+        MatrixView {
+            rows,
+            columns,
+            row_stride: self.row_stride,
+            col_stride: self.col_stride,
+            data: &self.data[start..end],
+        }
+    }
+
+    /// Transposed view, no data is moved.
+    pub fn t(&self) -> MatrixView<'a, T> {
+        MatrixView {
+            rows: self.columns,
+            columns: self.rows,
+            row_stride: self.col_stride,
+            col_stride: self.row_stride,
+            data: self.data,
+        }
+    }
+
+    /// Stretches a `1 x n` row, an `n x 1` column or a `1 x 1` scalar to
+    /// `rows` x `columns` without copying, repeated elements get a stride of 0.
+    pub fn try_broadcast(
+        &self,
+        op: &'static str,
+        rows: usize,
+        columns: usize,
+    ) -> Result<MatrixView<'a, T>, ShapeError> {
+        let fits = |have: usize, want: usize| have == want || have == 1;
+        if !fits(self.rows, rows) || !fits(self.columns, columns) {
+            return Err(ShapeError {
+                op,
+                expected: (rows, columns),
+                actual: self.shape(),
+            });
+        }
+
+        Ok(MatrixView {
+            rows,
+            columns,
+            row_stride: if self.rows == rows {
+                self.row_stride
+            } else {
+                0
+            },
+            col_stride: if self.columns == columns {
+                self.col_stride
+            } else {
+                0
+            },
+            data: self.data,
+        })
+    }
+
+    pub fn broadcast(&self, rows: usize, columns: usize) -> MatrixView<'a, T> {
+        self.try_broadcast("broadcast", rows, columns)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Row-major backing slice, only available when the view has no gaps.
+    pub fn as_slice(&self) -> Option<&'a [T]> {
+        let contiguous =
+            self.col_stride == 1 && (self.row_stride == self.columns || self.rows <= 1);
+        if contiguous {
+            Some(&self.data[..self.rows * self.columns])
+        } else {
+            None
+        }
+    }
+
+    /// Row-major iterator over the elements.
+    pub fn iter(&self) -> impl Iterator<Item = T> + 'a {
+        let view = *self;
+        (0..view.rows).flat_map(move |row| (0..view.columns).map(move |col| view.get(row, col)))
+    }
+
+    pub fn to_matrix(self) -> Matrix<T> {
+        Matrix {
+            rows: self.rows,
+            columns: self.columns,
+            data: self.iter().collect(),
+        }
+    }
+
+    /// New matrix holding `f(element)` for every element.
+    pub fn map(self, f: impl Fn(T) -> T + Sync) -> Matrix<T> {
+        let mut result = self.to_matrix();
+        result.view_mut().map_inplace(f);
+        result
+    }
+
+    /// New matrix holding `f(element, other element)` for every element.
+    pub fn try_zip_with(
+        self,
+        other: &impl AsMatrixView<T>,
+        f: impl Fn(T, T) -> T + Sync,
+    ) -> Result<Matrix<T>, ShapeError> {
+        let mut result = self.to_matrix();
+        result.view_mut().try_zip_with_inplace(other, f)?;
+        Ok(result)
+    }
+
+    pub fn zip_with(self, other: &impl AsMatrixView<T>, f: impl Fn(T, T) -> T + Sync) -> Matrix<T> {
+        self.try_zip_with(other, f)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl<T: Float> std::ops::Index<(usize, usize)> for MatrixView<'_, T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(row < self.rows);
+        assert!(col < self.columns);
+
+        &self.data[row * self.row_stride + col * self.col_stride]
+    }
+}
+
+/// Borrowed, possibly strided, writable window into matrix data.
+#[derive(Debug)]
+pub struct MatrixViewMut<'a, T = f32> {
+    pub rows: usize,
+    pub columns: usize,
+    pub(crate) row_stride: usize,
+    pub(crate) col_stride: usize,
+    pub(crate) data: &'a mut [T],
+}
+
+impl<'a, T: Float> MatrixViewMut<'a, T> {
+    /// Views a row-major slice as a writable `rows` x `columns` matrix.
+    pub fn new(data: &'a mut [T], rows: usize, columns: usize) -> MatrixViewMut<'a, T> {
+        assert_eq!(data.len(), rows * columns);
+
+        MatrixViewMut {
+            rows,
+            columns,
+            row_stride: columns,
+            col_stride: 1,
+            data,
+        }
+    }
+
+    /// Shorter-lived copy of this view, so it can be split again.
+    pub fn reborrow(&mut self) -> MatrixViewMut<'_, T> {
+        MatrixViewMut {
+            rows: self.rows,
+            columns: self.columns,
+            row_stride: self.row_stride,
+            col_stride: self.col_stride,
+            data: self.data,
+        }
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> T {
+        assert!(row < self.rows);
+        assert!(col < self.columns);
+
+        self.data[row * self.row_stride + col * self.col_stride]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: T) {
+        assert!(row < self.rows);
+        assert!(col < self.columns);
+
+        self.data[row * self.row_stride + col * self.col_stride] = value;
+    }
+
+    pub fn row_mut(self, row: usize) -> MatrixViewMut<'a, T> {
+        let columns = self.columns;
+        self.block_mut(row, 0, 1, columns)
+    }
+
+    pub fn column_mut(self, col: usize) -> MatrixViewMut<'a, T> {
+        let rows = self.rows;
+        self.block_mut(0, col, rows, 1)
+    }
+
+    pub fn block_mut(
+        self,
+        row: usize,
+        col: usize,
+        rows: usize,
+        columns: usize,
+    ) -> MatrixViewMut<'a, T> {
+        assert!(row + rows <= self.rows);
+        assert!(col + columns <= self.columns);
+
+        let start = (row * self.row_stride + col * self.col_stride).min(self.data.len());
+        let end = start + span(rows, columns, self.row_stride, self.col_stride);
+
+        MatrixViewMut {
+            rows,
+            columns,
+            row_stride: self.row_stride,
+            col_stride: self.col_stride,
+            data: &mut self.data[start..end],
+        }
+    }
+
+    /// Transposed view, no data is moved.
+    pub fn t(self) -> MatrixViewMut<'a, T> {
+        MatrixViewMut {
+            rows: self.columns,
+            columns: self.rows,
+            row_stride: self.col_stride,
+            col_stride: self.row_stride,
+            data: self.data,
+        }
+    }
+
+    /// Whether rows occupy disjoint ranges of the backing slice.
+    pub(crate) fn rows_disjoint(&self) -> bool {
+        self.columns == 0 || (self.columns - 1) * self.col_stride < self.row_stride
+    }
+
+    /// Splits into rows `..row` and `row..`, see `rows_disjoint`.
+    pub(crate) fn split_rows_mut(self, row: usize) -> (MatrixViewMut<'a, T>, MatrixViewMut<'a, T>) {
+        assert!(row <= self.rows);
+        assert!(self.rows_disjoint());
+
+        let at = (row * self.row_stride).min(self.data.len());
+        let (top, bottom) = self.data.split_at_mut(at);
+
+        (
+            MatrixViewMut {
+                rows: row,
+                columns: self.columns,
+                row_stride: self.row_stride,
+                col_stride: self.col_stride,
+                data: top,
+            },
+            MatrixViewMut {
+                rows: self.rows - row,
+                columns: self.columns,
+                row_stride: self.row_stride,
+                col_stride: self.col_stride,
+                data: bottom,
+            },
+        )
+    }
+
+    /// Applies `f` to every element, passing the matching element of `other`.
+    fn zip_apply(
+        &mut self,
+        op: &'static str,
+        other: MatrixView<T>,
+        f: impl Fn(T, T) -> T + Sync,
+    ) -> Result<(), ShapeError> {
+        ShapeError::check(op, self.shape(), other.shape())?;
+
+        #[cfg(feature = "parallel")]
+        if self.rows_disjoint() && parallel::worth_splitting(self.rows * self.columns) {
+            parallel::for_row_blocks(parallel::threads(), self.reborrow(), |row, mut block| {
+                let other = other.block(row, 0, block.rows, block.columns);
+                block.zip_apply_serial(other, &f)
+            });
+            return Ok(());
+        }
+
+        self.zip_apply_serial(other, f);
+        Ok(())
+    }
+
+    fn zip_apply_serial(&mut self, other: MatrixView<T>, f: impl Fn(T, T) -> T) {
+        for row in 0..self.rows {
+            for col in 0..self.columns {
+                let index = row * self.row_stride + col * self.col_stride;
+                self.data[index] = f(self.data[index], other.get(row, col));
+            }
+        }
+    }
+
+    /// Replaces every element with `f(element)`.
+    pub fn map_inplace(&mut self, f: impl Fn(T) -> T + Sync) {
+        #[cfg(feature = "parallel")]
+        if self.rows_disjoint() && parallel::worth_splitting(self.rows * self.columns) {
+            parallel::for_row_blocks(parallel::threads(), self.reborrow(), |_, mut block| {
+                block.apply_serial(&f)
+            });
+            return;
+        }
+
+        self.apply_serial(f);
+    }
+
+    fn apply_serial(&mut self, f: impl Fn(T) -> T) {
+        for row in 0..self.rows {
+            for col in 0..self.columns {
+                let index = row * self.row_stride + col * self.col_stride;
+                self.data[index] = f(self.data[index]);
+            }
+        }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.map_inplace(|_| value);
+    }
+
+    /// Replaces every element with `f(element, other element)`.
+    pub fn try_zip_with_inplace(
+        &mut self,
+        other: &impl AsMatrixView<T>,
+        f: impl Fn(T, T) -> T + Sync,
+    ) -> Result<(), ShapeError> {
+        self.zip_apply("zip_with", other.view(), f)
+    }
+
+    pub fn zip_with_inplace(&mut self, other: &impl AsMatrixView<T>, f: impl Fn(T, T) -> T + Sync) {
+        self.try_zip_with_inplace(other, f)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    pub fn try_copy_from(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.zip_apply("copy_from", other.view(), |_, b| b)
+    }
+
+    pub fn copy_from(&mut self, other: &impl AsMatrixView<T>) {
+        self.try_copy_from(other)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// `zip_apply` with `other` broadcast to the shape of `self` first.
+    fn broadcast_apply(
+        &mut self,
+        op: &'static str,
+        other: MatrixView<T>,
+        f: impl Fn(T, T) -> T + Sync,
+    ) -> Result<(), ShapeError> {
+        let other = other.try_broadcast(op, self.rows, self.columns)?;
+        self.zip_apply(op, other, f)
+    }
+
+    /// Adds `other`, which may also be a `1 x columns` row added to every row,
+    /// a `rows x 1` column added to every column or a `1 x 1` scalar.
+    pub fn try_add(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.broadcast_apply("add", other.view(), |a, b| a + b)
+    }
+
+    pub fn add(&mut self, other: &impl AsMatrixView<T>) {
+        self.try_add(other).unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// Broadcasting subtraction, see `try_add`.
+    pub fn try_sub(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.broadcast_apply("sub", other.view(), |a, b| a - b)
+    }
+
+    pub fn sub(&mut self, other: &impl AsMatrixView<T>) {
+        self.try_sub(other).unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// Broadcasting elementwise product, see `try_add`.
+    pub fn try_hadamard(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.broadcast_apply("hadamard", other.view(), |a, b| a * b)
+    }
+
+    pub fn hadamard(&mut self, other: &impl AsMatrixView<T>) {
+        self.try_hadamard(other)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// Broadcasting elementwise division, see `try_add`.
+    pub fn try_div(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.broadcast_apply("div", other.view(), |a, b| a / b)
+    }
+
+    pub fn div(&mut self, other: &impl AsMatrixView<T>) {
+        self.try_div(other).unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    pub fn dotf(&mut self, value: T) {
+        self.map_inplace(|a| a * value);
+    }
+
+    pub fn sigmoid(&mut self) {
+        self.map_inplace(sigmoid);
+    }
+
+    /// Product of `a` and `b` into the view, `op` names the caller in errors.
+    pub(crate) fn matmul(
+        &mut self,
+        op: &'static str,
+        a: MatrixView<T>,
+        b: MatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        ShapeError::check(op, (a.columns, b.columns), b.shape())?;
+        ShapeError::check(op, (a.rows, b.columns), self.shape())?;
+
+        matmul::gemm(self, &a, &b);
+        Ok(())
+    }
+
+    /// Writes `a · b` into the view without allocating.
+    pub fn try_dot_into(
+        &mut self,
+        a: &impl AsMatrixView<T>,
+        b: &impl AsMatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        self.matmul("dot", a.view(), b.view())
+    }
+
+    pub fn dot_into(&mut self, a: &impl AsMatrixView<T>, b: &impl AsMatrixView<T>) {
+        self.try_dot_into(a, b)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+}
+
+impl<T: Float> std::ops::Index<(usize, usize)> for MatrixViewMut<'_, T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(row < self.rows);
+        assert!(col < self.columns);
+
+        &self.data[row * self.row_stride + col * self.col_stride]
+    }
+}
+
+impl<T: Float> std::ops::IndexMut<(usize, usize)> for MatrixViewMut<'_, T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(row < self.rows);
+        assert!(col < self.columns);
+
+        &mut self.data[row * self.row_stride + col * self.col_stride]
+    }
+}
+
+impl<T: Float> Matrix<T> {
+    pub fn view_mut(&mut self) -> MatrixViewMut<'_, T> {
+        MatrixViewMut {
+            rows: self.rows,
+            columns: self.columns,
+            row_stride: self.columns,
+            col_stride: 1,
+            data: &mut self.data,
+        }
+    }
+
+    pub fn row(&self, row: usize) -> MatrixView<'_, T> {
+        self.view().row(row)
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> MatrixViewMut<'_, T> {
+        self.view_mut().row_mut(row)
+    }
+
+    pub fn column(&self, col: usize) -> MatrixView<'_, T> {
+        self.view().column(col)
+    }
+
+    pub fn column_mut(&mut self, col: usize) -> MatrixViewMut<'_, T> {
+        self.view_mut().column_mut(col)
+    }
+
+    pub fn block(&self, row: usize, col: usize, rows: usize, columns: usize) -> MatrixView<'_, T> {
+        self.view().block(row, col, rows, columns)
+    }
+
+    pub fn block_mut(
+        &mut self,
+        row: usize,
+        col: usize,
+        rows: usize,
+        columns: usize,
+    ) -> MatrixViewMut<'_, T> {
+        self.view_mut().block_mut(row, col, rows, columns)
+    }
+
+    /// Transposed view, no data is moved.
+    pub fn t(&self) -> MatrixView<'_, T> {
+        self.view().t()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_views_share_storage() {
+        let mut m: Matrix = Matrix::from_2d_vec(&vec![
+            vec![1., 2., 3.], //
+            vec![4., 5., 6.], //
+            vec![7., 8., 9.], //
+        ]);
+
+        assert_eq!(m.row(1).iter().collect::<Vec<_>>(), vec![4., 5., 6.]);
+        assert_eq!(m.column(2).iter().collect::<Vec<_>>(), vec![3., 6., 9.]);
+        assert_eq!(m.block(1, 1, 2, 2).to_matrix().data, vec![5., 6., 8., 9.]);
+        assert_eq!(
+            m.t().block(0, 1, 2, 2).to_matrix().data,
+            vec![4., 7., 5., 8.]
+        );
+        assert!(m.row(2).as_slice().is_some());
+        assert!(m.column(0).as_slice().is_none());
+
+        m.block_mut(0, 1, 2, 2).t().fill(0.);
+        assert_eq!(m.data, vec![1., 0., 0., 4., 0., 0., 7., 8., 9.]);
+
+        let mut dst = Matrix::new(1, 1);
+        dst.dot_into(&m.row(2), &m.column(0));
+        assert_eq!(dst.get(0, 0), 7. + 8. * 4. + 9. * 7.);
+    }
+
+    #[test]
+    fn test_broadcasting() {
+        let mut batch: Matrix = Matrix::from_2d_vec(&vec![
+            vec![1., 2., 3.], //
+            vec![4., 5., 6.], //
+        ]);
+        let bias = Matrix::from_2d_vec(&vec![vec![10., 20., 30.]]);
+        let scale = Matrix::from_2d_vec(&vec![vec![2.], vec![-1.]]);
+        let half = Matrix::from_2d_vec(&vec![vec![0.5]]);
+
+        batch.add(&bias);
+        assert_eq!(batch.data, vec![11., 22., 33., 14., 25., 36.]);
+        batch.hadamard(&scale);
+        assert_eq!(batch.data, vec![22., 44., 66., -14., -25., -36.]);
+        batch.div(&half);
+        batch.sub(&bias.row(0).block(0, 1, 1, 1));
+        assert_eq!(batch.data, vec![24., 68., 112., -48., -70., -92.]);
+
+        let err = batch.try_add(&Matrix::new(1, 2)).unwrap_err();
+        assert_eq!((err.op, err.expected, err.actual), ("add", (2, 3), (1, 2)));
+        assert!(batch.try_add(&Matrix::new(3, 1)).is_err());
+        assert!(bias.clone().try_add(&batch).is_err());
+        assert_eq!(half.view().broadcast(2, 2).to_matrix().data, vec![0.5; 4]);
+    }
+}
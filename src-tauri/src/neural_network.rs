@@ -1,382 +1,623 @@
-use crate::data::RNG_SEED;
+use crate::activation::{check_derivative, Activation, Builtin};
 use crate::data::{LEARN_RATE, STEP};
-use rand::{rngs::StdRng, Rng, SeedableRng};
-
-pub fn sigmoid(x: f32) -> f32 {
-    1. / (1. + (-x).exp())
+use crate::display::MatrixFormat;
+use crate::encoding::MatrixData;
+use crate::error::{DerivativeError, ShapeError};
+use crate::float::Float;
+use crate::layer::{Dense, Layer, Parameter};
+use crate::loss::Loss;
+use crate::matrix_view::AsMatrixView;
+use crate::optimizer::{Optimizer, Sgd};
+use crate::regularization::{Clip, Regularization};
+use crate::schedule::{Constant, Schedule};
+use crate::sequential::Sequential;
+use crate::sparse::CsrMatrix;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub fn sigmoid<T: Float>(x: T) -> T {
+    T::ONE / (T::ONE + (-x).exp())
 }
 
-#[derive(Debug, Clone)]
-pub struct Matrix {
+/// Serializes as `{ rows, columns, data }`, deserializing checks that `data`
+/// holds `rows * columns` elements.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "MatrixData<T>")]
+pub struct Matrix<T = f32> {
     pub rows: usize,
     pub columns: usize,
-    pub data: Vec<f32>,
+    pub data: Vec<T>,
 }
 
-impl Matrix {
-    pub fn new(rows: usize, columns: usize) -> Matrix {
+impl<T: Float> Matrix<T> {
+    pub fn new(rows: usize, columns: usize) -> Matrix<T> {
         Matrix {
             rows,
             columns,
-            data: vec![0.; rows * columns],
+            data: vec![T::ZERO; rows * columns],
         }
     }
 
-    pub fn fill(&mut self, value: f32) {
-        for row in 0..self.rows {
-            for col in 0..self.columns {
-                self.set(row, col, value);
-            }
-        }
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.columns)
     }
 
-    pub fn fill_rand(&mut self, low: f32, high: f32) {
-        let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    pub fn fill(&mut self, value: T) {
+        self.data.fill(value);
+    }
 
-        for index in 0..self.data.len() {
-            self.data[index] = rng.gen_range(low..high);
+    /// Reallocates as `rows` rows of zeros unless it already has that many.
+    pub fn resize_rows(&mut self, rows: usize) {
+        if self.rows != rows {
+            *self = Matrix::new(rows, self.columns);
         }
     }
 
-    pub fn add(&mut self, other: &Matrix) {
-        assert_eq!(self.rows, other.rows);
-        assert_eq!(self.columns, other.columns);
+    pub fn try_copy_from(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.view_mut().try_copy_from(other)
+    }
 
-        for row in 0..self.rows {
-            for col in 0..self.columns {
-                self.set(row, col, self.get(row, col) + other.get(row, col));
-            }
-        }
+    pub fn copy_from(&mut self, other: &impl AsMatrixView<T>) {
+        self.view_mut().copy_from(other);
     }
 
-    pub fn sub(&mut self, other: &Matrix) {
-        assert_eq!(self.rows, other.rows);
-        assert_eq!(self.columns, other.columns);
+    pub fn try_add(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.view_mut().try_add(other)
+    }
 
-        for row in 0..self.rows {
-            for col in 0..self.columns {
-                self.set(row, col, self.get(row, col) - other.get(row, col));
-            }
-        }
+    pub fn add(&mut self, other: &impl AsMatrixView<T>) {
+        self.view_mut().add(other);
     }
 
-    pub fn set(&mut self, row: usize, col: usize, value: f32) {
-        assert!(row < self.rows);
-        assert!(col < self.columns);
+    pub fn try_sub(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.view_mut().try_sub(other)
+    }
 
-        self.data[row * self.columns + col] = value;
+    pub fn sub(&mut self, other: &impl AsMatrixView<T>) {
+        self.view_mut().sub(other);
     }
 
-    // FIX REPLACE SET AND GET BY ONLY ONE FUNCTION CALLED AT
-    pub fn get(&self, row: usize, col: usize) -> f32 {
-        assert!(row < self.rows);
-        assert!(col < self.columns);
+    pub fn try_hadamard(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.view_mut().try_hadamard(other)
+    }
+
+    pub fn hadamard(&mut self, other: &impl AsMatrixView<T>) {
+        self.view_mut().hadamard(other);
+    }
+
+    pub fn try_div(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.view_mut().try_div(other)
+    }
 
-        self.data[row * self.columns + col]
+    pub fn div(&mut self, other: &impl AsMatrixView<T>) {
+        self.view_mut().div(other);
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: T) {
+        self[(row, col)] = value;
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> T {
+        self[(row, col)]
     }
 
     pub fn sigmoid(&mut self) {
-        for row in 0..self.rows {
-            for col in 0..self.columns {
-                self.set(row, col, sigmoid(self.get(row, col)));
-            }
-        }
+        self.view_mut().sigmoid();
     }
 
-    pub fn row(&self, row: usize) -> Matrix {
-        let mut result = Matrix::new(1, self.columns);
+    pub fn map(&self, f: impl Fn(T) -> T + Sync) -> Matrix<T> {
+        self.view().map(f)
+    }
 
-        for col in 0..self.columns {
-            result.set(0, col, self.get(row, col));
-        }
+    pub fn map_inplace(&mut self, f: impl Fn(T) -> T + Sync) {
+        self.view_mut().map_inplace(f);
+    }
 
-        result
+    pub fn try_zip_with(
+        &self,
+        other: &impl AsMatrixView<T>,
+        f: impl Fn(T, T) -> T + Sync,
+    ) -> Result<Matrix<T>, ShapeError> {
+        self.view().try_zip_with(other, f)
     }
 
-    pub fn dot(&mut self, other: &Matrix) {
-        assert_eq!(self.columns, other.rows);
+    pub fn zip_with(
+        &self,
+        other: &impl AsMatrixView<T>,
+        f: impl Fn(T, T) -> T + Sync,
+    ) -> Matrix<T> {
+        self.view().zip_with(other, f)
+    }
 
-        let mut result = Matrix::new(self.rows, other.columns);
+    pub fn try_zip_with_inplace(
+        &mut self,
+        other: &impl AsMatrixView<T>,
+        f: impl Fn(T, T) -> T + Sync,
+    ) -> Result<(), ShapeError> {
+        self.view_mut().try_zip_with_inplace(other, f)
+    }
 
-        for row in 0..self.rows {
-            for col in 0..other.columns {
-                let mut sum = 0.;
-                for k in 0..self.columns {
-                    sum += self.get(row, k) * other.get(k, col);
-                }
-                result.set(row, col, sum);
-            }
-        }
+    pub fn zip_with_inplace(&mut self, other: &impl AsMatrixView<T>, f: impl Fn(T, T) -> T + Sync) {
+        self.view_mut().zip_with_inplace(other, f);
+    }
 
+    pub fn try_dot(&mut self, other: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        let mut result = Matrix::new(self.rows, other.view().columns);
+        result.try_dot_into(self, other)?;
         *self = result;
+        Ok(())
     }
 
-    pub fn dotf(&mut self, value: f32) {
-        for row in 0..self.rows {
-            for col in 0..self.columns {
-                self.set(row, col, self.get(row, col) * value);
-            }
-        }
+    pub fn dot(&mut self, other: &impl AsMatrixView<T>) {
+        self.try_dot(other).unwrap_or_else(|err| panic!("{}", err));
     }
 
-    pub fn from_2d_vec(data: &Vec<Vec<f32>>) -> Matrix {
-        let rows = data.len();
-        let columns = data[0].len();
+    /// Writes `a · b` into `self` without allocating.
+    pub fn try_dot_into(
+        &mut self,
+        a: &impl AsMatrixView<T>,
+        b: &impl AsMatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        self.view_mut().try_dot_into(a, b)
+    }
 
-        let mut result = Matrix::new(rows, columns);
+    pub fn dot_into(&mut self, a: &impl AsMatrixView<T>, b: &impl AsMatrixView<T>) {
+        self.view_mut().dot_into(a, b);
+    }
 
-        for row in 0..rows {
-            for col in 0..columns {
-                result.set(row, col, data[row][col]);
-            }
-        }
+    /// Writes `a · bᵀ` into `self` without allocating.
+    pub fn try_dot_transposed_into(
+        &mut self,
+        a: &impl AsMatrixView<T>,
+        b: &impl AsMatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        self.view_mut()
+            .matmul("dot_transposed", a.view(), b.view().t())
+    }
+
+    pub fn dot_transposed_into(&mut self, a: &impl AsMatrixView<T>, b: &impl AsMatrixView<T>) {
+        self.try_dot_transposed_into(a, b)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// Writes `aᵀ · b` into `self` without allocating.
+    pub fn try_transposed_dot_into(
+        &mut self,
+        a: &impl AsMatrixView<T>,
+        b: &impl AsMatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        self.view_mut()
+            .matmul("transposed_dot", a.view().t(), b.view())
+    }
 
-        result
+    pub fn transposed_dot_into(&mut self, a: &impl AsMatrixView<T>, b: &impl AsMatrixView<T>) {
+        self.try_transposed_dot_into(a, b)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    pub fn dotf(&mut self, value: T) {
+        self.view_mut().dotf(value);
+    }
+
+    /// Panics on ragged rows, see `try_from_rows`.
+    pub fn from_2d_vec(data: &Vec<Vec<T>>) -> Matrix<T> {
+        Matrix::from_rows(data)
     }
 }
 
-// TODO improve display for matrices
-impl std::fmt::Display for Matrix {
+impl<T: Float> std::fmt::Display for Matrix<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut result = String::new();
+        self.view().fmt(f)
+    }
+}
 
-        for row in 0..self.rows {
-            if self.rows > 1 {
-                result += "\n";
-            }
-            for col in 0..self.columns {
-                result += &format!("{:.2} ", self.get(row, col));
-            }
+/// Size and activation of one layer in the `arch` given to `NeuralNetwork::new`.
+/// A bare size means a sigmoid layer, the activation of the input layer is ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerSpec {
+    pub size: usize,
+    pub activation: Builtin,
+}
+
+impl From<usize> for LayerSpec {
+    fn from(size: usize) -> Self {
+        LayerSpec {
+            size,
+            activation: Builtin::default(),
         }
+    }
+}
 
-        write!(f, "{}", result)
+impl From<(usize, Builtin)> for LayerSpec {
+    fn from((size, activation): (usize, Builtin)) -> Self {
+        LayerSpec { size, activation }
     }
 }
 
+/// Stack of `Dense` layers trained against a second network of the same
+/// shape holding the gradients, see `backprop` and `learn`.
 #[derive(Debug)]
-pub struct NeuralNetwork {
-    w: Vec<Matrix>,
-    b: Vec<Matrix>,
-    a: Vec<Matrix>,
+pub struct NeuralNetwork<T = f32> {
+    model: Sequential<T, Dense<T>>,
+    /// Rate of every `learn` step.
+    schedule: Box<dyn Schedule>,
+    /// `learn` steps taken since the schedule was set.
+    step: usize,
 }
 
-impl NeuralNetwork {
-    pub fn new(arch: &[usize]) -> Self {
+impl<T: Float> NeuralNetwork<T> {
+    /// `arch` lists the layer sizes, `&[2, 2, 1]`, or sizes with activations,
+    /// `&[(2, Linear), (4, Relu), (1, Linear)]`.
+    pub fn new<L: Into<LayerSpec> + Copy>(arch: &[L]) -> Self {
+        let arch: Vec<LayerSpec> = arch.iter().map(|&layer| layer.into()).collect();
         assert!(arch.len() > 1);
-        assert!(arch[..].iter().all(|&x| x > 0));
-
-        let mut brain = Self {
-            w: Vec::new(),
-            b: Vec::new(),
-            a: Vec::new(),
-        };
+        assert!(arch[..].iter().all(|x| x.size > 0));
 
-        brain.a.push(Matrix::new(1, arch[0]));
+        let mut model = Sequential::with_inputs(arch[0].size);
+        for pair in arch.windows(2) {
+            let dense = Dense::new(pair[0].size, pair[1].size, pair[1].activation);
+            model.push_boxed(Box::new(dense));
+        }
 
-        for i in 1..arch.len() {
-            brain.w.push(Matrix::new(arch[i - 1], arch[i]));
-            brain.b.push(Matrix::new(1, arch[i]));
-            brain.a.push(Matrix::new(1, arch[i]));
+        Self {
+            model,
+            schedule: Box::new(Constant {
+                rate: LEARN_RATE as f64,
+            }),
+            step: 0,
         }
+    }
 
-        brain
+    /// Replaces the activation of `layer`, 1 is the first layer after the input,
+    /// once `check_derivative` accepts its derivative.
+    pub fn try_set_activation(
+        &mut self,
+        layer: usize,
+        activation: impl Activation<T> + 'static,
+    ) -> Result<(), DerivativeError> {
+        assert!(layer > 0 && layer <= self.model.layers().len());
+
+        let step = T::from_f64(STEP as f64 / 100.);
+        check_derivative(&activation, step, T::from_f64(1e-2))?;
+        self.model.layers_mut()[layer - 1].set_activation(activation);
+        Ok(())
     }
 
-    pub fn rand(&mut self, low: f32, high: f32) {
-        for i in 0..self.w.len() {
-            self.w[i].fill_rand(low, high);
-            self.b[i].fill_rand(low, high);
-        }
+    pub fn set_activation(&mut self, layer: usize, activation: impl Activation<T> + 'static) {
+        self.try_set_activation(layer, activation)
+            .unwrap_or_else(|err| panic!("{}", err));
     }
 
-    // TODO change for impl Display
-    pub fn to_string(&mut self) -> String {
-        let mut result = "[\n".to_string();
-        for i in 0..self.w.len() {
-            result += &format!("_ _ a[{}] {:?}\n", i, self.a[i]);
-            result += "\n";
-            result += &format!("_ _ w[{}] {:?}\n", i, self.w[i]);
-            result += &format!("_ _ b[{}] {:?}\n", i, self.b[i]);
-        }
-        result += &format!("_ _ a[{}] {:?}\n", self.a.len() - 1, self.output());
-        result += "]";
-        result
+    /// Sets the penalty on the weights of `layer`, numbered like in `set_activation`.
+    pub fn set_regularization(&mut self, layer: usize, regularization: Regularization) {
+        assert!(layer > 0 && layer <= self.model.layers().len());
+
+        self.model.layers_mut()[layer - 1].set_regularization(regularization);
     }
 
-    pub fn forward(&mut self) {
-        for i in 0..self.w.len() {
-            self.a[i + 1] = self.a[i].clone();
-            self.a[i + 1].dot(&self.w[i]);
+    /// Sum of the regularization penalties of every layer.
+    pub fn penalty(&self) -> T {
+        self.model.penalty()
+    }
 
-            self.a[i + 1].add(&self.b[i]);
+    /// Replaces the loss behind `cost` and `backprop`, `Mse` by default.
+    pub fn set_loss(&mut self, loss: impl Loss<T> + 'static) {
+        self.model.set_loss(loss);
+    }
 
-            self.a[i + 1].sigmoid();
-        }
+    /// Replaces the learning rate schedule of `learn`, a constant `LEARN_RATE`
+    /// by default, and starts counting steps from 0 again.
+    pub fn set_schedule(&mut self, schedule: impl Schedule + 'static) {
+        self.schedule = Box::new(schedule);
+        self.step = 0;
     }
 
-    pub fn input(&mut self, input: &Matrix) {
-        assert_eq!(input.rows, 1);
-        assert_eq!(input.columns, self.w[0].rows);
+    /// Passes the loss of a finished epoch to the schedule.
+    pub fn end_epoch(&mut self, loss: T) {
+        self.schedule.epoch_end(loss.to_f64());
+    }
 
-        self.a[0] = input.clone();
+    /// Uniform weights and biases in `low..high`, seeded from `RNG_SEED`.
+    pub fn rand(&mut self, low: T, high: T) {
+        self.model.rand(low, high);
     }
 
-    pub fn output(&mut self) -> &mut Matrix {
-        let layers = self.a.len();
-        &mut self.a[layers - 1]
+    pub fn rand_with(&mut self, rng: &mut impl Rng, low: T, high: T) {
+        self.model.rand_with(rng, low, high);
     }
 
-    pub fn cost(&mut self, truth_in: &Matrix, truth_out: &Matrix) -> f32 {
-        assert_eq!(truth_in.rows, truth_out.rows);
-        assert_eq!(truth_out.columns, self.output().columns);
+    /// Weight and bias tables of every layer, for the `print` event panel.
+    pub fn to_html(&self, format: &MatrixFormat) -> String {
+        let mut html = String::new();
+        for (i, dense) in self.model.layers().iter().enumerate() {
+            let w = dense.w();
+            html += &format!(
+                "layer {}: {} -> {}, {}",
+                i + 1,
+                w.rows,
+                w.columns,
+                dense.activation().name()
+            );
+            html += &format!("<br>w{}", format.render_html(w));
+            html += &format!("b{}", format.render_html(dense.b()));
+        }
+        html
+    }
 
-        let mut cost = 0.;
+    pub fn forward(&mut self) {
+        self.model.forward();
+    }
 
-        for row in 0..truth_in.rows {
-            let truth_in = truth_in.row(row);
-            let truth_out = truth_out.row(row);
+    /// Runs a batch of sparse inputs through the network, see
+    /// `Sequential::try_forward_sparse`.
+    pub fn try_forward_sparse(&mut self, input: &CsrMatrix<T>) -> Result<(), ShapeError> {
+        self.model.try_forward_sparse(input)
+    }
 
-            self.input(&truth_in);
-            self.forward();
+    pub fn forward_sparse(&mut self, input: &CsrMatrix<T>) {
+        self.try_forward_sparse(input)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
 
-            for col in 0..truth_out.columns {
-                let d = self.output().get(0, col) - truth_out.get(0, col);
-                cost += d * d;
-            }
-        }
+    /// Sets a batch of inputs, one sample per row.
+    pub fn try_input(&mut self, input: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        self.model.try_input(input)
+    }
+
+    pub fn input(&mut self, input: &impl AsMatrixView<T>) {
+        self.try_input(input)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    pub fn output(&self) -> &Matrix<T> {
+        self.model.output()
+    }
+
+    pub fn try_cost(
+        &mut self,
+        truth_in: &impl AsMatrixView<T>,
+        truth_out: &impl AsMatrixView<T>,
+    ) -> Result<T, ShapeError> {
+        self.model.try_cost(truth_in, truth_out)
+    }
 
-        cost / truth_in.rows as f32
+    pub fn cost(&mut self, truth_in: &impl AsMatrixView<T>, truth_out: &impl AsMatrixView<T>) -> T {
+        self.try_cost(truth_in, truth_out)
+            .unwrap_or_else(|err| panic!("{}", err))
     }
 
-    pub fn finite_diff(&mut self, grad: &mut NeuralNetwork, truth_in: &Matrix, truth_out: &Matrix) {
-        let mut saved;
+    pub fn finite_diff(
+        &mut self,
+        grad: &mut NeuralNetwork<T>,
+        truth_in: &impl AsMatrixView<T>,
+        truth_out: &impl AsMatrixView<T>,
+    ) {
+        let step = T::from_f64(STEP as f64);
 
         let cost_start = self.cost(truth_in, truth_out);
 
-        for layer_index in 0..self.w.len() {
-            for row in 0..self.w[layer_index].rows {
-                for col in 0..self.w[layer_index].columns {
-                    saved = self.w[layer_index].get(row, col);
-                    self.w[layer_index].set(row, col, saved + STEP);
+        for layer in 0..self.model.layers().len() {
+            for p in 0..self.model.layers()[layer].parameters().len() {
+                for index in 0..self.value_mut(layer, p).data.len() {
+                    let saved = self.value_mut(layer, p).data[index];
+                    self.value_mut(layer, p).data[index] = saved + step;
                     let cost_new = self.cost(truth_in, truth_out);
-                    grad.w[layer_index].set(row, col, (cost_new - cost_start) / STEP);
-                    self.w[layer_index].set(row, col, saved);
+                    grad.value_mut(layer, p).data[index] = (cost_new - cost_start) / step;
+                    self.value_mut(layer, p).data[index] = saved;
                 }
             }
+        }
+    }
 
-            for row in 0..self.b[layer_index].rows {
-                for col in 0..self.b[layer_index].columns {
-                    saved = self.b[layer_index].get(row, col);
-                    self.b[layer_index].set(row, col, saved + STEP);
-                    let cost_new = self.cost(truth_in, truth_out);
-                    grad.b[layer_index].set(row, col, (cost_new - cost_start) / STEP);
-                    self.b[layer_index].set(row, col, saved);
-                }
+    /// Parameter `p` of `layer`, counting from 0 in the order of `Layer::parameters`.
+    fn value_mut(&mut self, layer: usize, p: usize) -> &mut Matrix<T> {
+        self.model.layers_mut()[layer]
+            .parameters_mut()
+            .swap_remove(p)
+            .value
+    }
+
+    /// Writes the gradients of `cost` over the dataset into the weights and
+    /// biases of `g`.
+    pub fn try_backprop(
+        &mut self,
+        g: &mut NeuralNetwork<T>,
+        ti: &impl AsMatrixView<T>,
+        to: &impl AsMatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        self.model.try_backprop(ti, to)?;
+
+        for (dense, g) in self.model.layers().iter().zip(g.model.layers_mut()) {
+            for (gradient, p) in dense.gradients().into_iter().zip(g.parameters_mut()) {
+                p.value.copy_from(gradient);
             }
         }
+
+        Ok(())
     }
 
-    pub fn backprop(&mut self, g: &mut NeuralNetwork, ti: &Matrix, to: &Matrix) {
-        assert_eq!(ti.rows, to.rows);
-        assert_eq!(self.output().columns, to.columns);
-        let n: usize = ti.rows;
+    pub fn backprop(
+        &mut self,
+        g: &mut NeuralNetwork<T>,
+        ti: &impl AsMatrixView<T>,
+        to: &impl AsMatrixView<T>,
+    ) {
+        self.try_backprop(g, ti, to)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
 
-        g.fill(0.);
+    /// Gradient descent step along `grad` at the rate the schedule gives for
+    /// this step, returns that rate.
+    pub fn learn(&mut self, grad: &mut NeuralNetwork<T>) -> f64 {
+        let rate = self.schedule.rate(self.step);
+        self.step += 1;
+        self.learn_with(grad, &mut Sgd::new(rate));
+        rate
+    }
 
-        // i - current sample
-        // l - current layer
-        // j - current activation
-        // k - previous activation
+    /// Steps along `grad` with `optimizer` at its own rate, without the
+    /// schedule. `optimizer` has to be used with this network only.
+    pub fn learn_with(&mut self, grad: &mut NeuralNetwork<T>, optimizer: &mut impl Optimizer<T>) {
+        optimizer.step(&mut self.parameters_mut(grad));
+    }
 
-        for i in 0..n {
-            self.input(&ti.row(i));
-            self.forward();
+    /// Clips `grad` before `learn`, returns its global norm from before clipping.
+    pub fn clip_gradients(&mut self, grad: &mut NeuralNetwork<T>, clip: Clip) -> T {
+        clip.apply(&mut self.parameters_mut(grad))
+    }
 
-            for j in 0..=self.w.len() {
-                g.a[j].fill(0.);
-            }
+    /// Weights and biases paired with their gradients in `grad`.
+    fn parameters_mut<'a>(&'a mut self, grad: &'a mut NeuralNetwork<T>) -> Vec<Parameter<'a, T>> {
+        let parameters = self.model.parameters_mut().into_iter();
+        let grads = grad.model.parameters_mut().into_iter();
+        parameters
+            .zip(grads)
+            .map(|(p, g)| Parameter {
+                value: p.value,
+                grad: g.value,
+                decay: p.decay,
+            })
+            .collect()
+    }
 
-            for j in 0..to.columns {
-                let d = self.output().get(0, j) - to.get(i, j);
-                g.output().set(0, j, d);
-            }
+    /// Sets every weight and bias to `value`.
+    pub fn fill(&mut self, value: T) {
+        for p in self.model.parameters_mut() {
+            p.value.fill(value);
+        }
+    }
+}
 
-            // TEST for (size_t l = nn.count; l > 0; --l) {
-            for l in (1..=self.w.len()).rev() {
-                for j in 0..self.a[l].columns {
-                    let a = self.a[l].get(0, j);
-                    let da = g.a[l].get(0, j);
-
-                    let mut nb = g.b[l - 1].get(0, j);
-                    nb += 2. * da * a * (1. - a);
-                    g.b[l - 1].set(0, j, nb);
-
-                    for k in 0..self.a[l - 1].columns {
-                        // j - weight matrix col
-                        // k - weight matrix row
-                        let pa = self.a[l - 1].get(0, k);
-                        let w = self.w[l - 1].get(k, j);
-
-                        let mut naw = g.w[l - 1].get(k, j);
-                        naw += 2. * da * a * (1. - a) * pa;
-                        g.w[l - 1].set(k, j, naw);
-
-                        let mut na = g.a[l - 1].get(0, k);
-                        na += 2. * da * a * (1. - a) * w;
-                        g.a[l - 1].set(0, k, na);
-                    }
-                }
+impl<T: Float> std::fmt::Display for NeuralNetwork<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = MatrixFormat::from_formatter(f);
+        for (i, dense) in self.model.layers().iter().enumerate() {
+            let w = dense.w();
+            if i > 0 {
+                writeln!(f)?;
             }
+            writeln!(
+                f,
+                "layer {}: {} -> {}, {}",
+                i + 1,
+                w.rows,
+                w.columns,
+                dense.activation().name()
+            )?;
+            writeln!(f, "w\n{}", format.render(w))?;
+            write!(f, "b\n{}", format.render(dense.b()))?;
         }
+        Ok(())
+    }
+}
 
-        for i in 0..g.w.len() {
-            for j in 0..g.w[i].rows {
-                for k in 0..g.w[i].columns {
-                    let mut nw = g.w[i].get(j, k);
-                    nw /= n as f32;
-                    g.w[i].set(j, k, nw);
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            for j in 0..g.b[i].rows {
-                for k in 0..g.b[i].columns {
-                    let mut nb = g.b[i].get(j, k);
-                    nb /= n as f32;
-                    g.b[i].set(j, k, nb);
-                }
+    #[test]
+    fn test_matrix_dot_transposed_variants() {
+        let a: Matrix = Matrix::from_2d_vec(&vec![vec![1., 2., 3.], vec![4., 5., 6.]]);
+        let b = Matrix::from_2d_vec(&vec![vec![7., 8.], vec![9., 10.], vec![11., 12.]]);
+
+        let mut ab = Matrix::new(2, 2);
+        ab.dot_into(&a, &b);
+        assert_eq!(ab.data, vec![58., 64., 139., 154.]);
+
+        let mut bt = Matrix::new(2, 3);
+        for row in 0..b.rows {
+            for col in 0..b.columns {
+                bt.set(col, row, b.get(row, col));
             }
         }
+
+        let mut abt = Matrix::new(2, 2);
+        abt.dot_transposed_into(&a, &bt);
+        assert_eq!(abt.data, ab.data);
+
+        let mut ba = Matrix::new(3, 3);
+        ba.dot_into(&b, &a);
+        let mut btta = Matrix::new(3, 3);
+        btta.transposed_dot_into(&bt, &a);
+        assert_eq!(btta.data, ba.data);
     }
 
-    pub fn learn(&mut self, grad: &mut NeuralNetwork) {
-        for layer_index in 0..self.w.len() {
-            grad.w[layer_index].dotf(LEARN_RATE);
-            self.w[layer_index].sub(&grad.w[layer_index]);
-            grad.b[layer_index].dotf(LEARN_RATE);
-            self.b[layer_index].sub(&grad.b[layer_index]);
-        }
+    #[test]
+    fn test_shape_errors() {
+        let mut a: Matrix = Matrix::new(2, 3);
+        let err = a.try_add(&Matrix::new(3, 2)).unwrap_err();
+        assert_eq!((err.op, err.expected, err.actual), ("add", (2, 3), (3, 2)));
+
+        let err = a.try_dot(&Matrix::new(2, 2)).unwrap_err();
+        assert_eq!((err.op, err.expected, err.actual), ("dot", (3, 2), (2, 2)));
+        assert_eq!(err.to_string(), "dot: expected shape 3x2, got 2x2");
+        assert_eq!(a.shape(), (2, 3));
+
+        let mut nn: NeuralNetwork = NeuralNetwork::new(&[2, 3, 1]);
+        let mut grad = NeuralNetwork::new(&[2, 3, 1]);
+        let err = nn.try_input(&Matrix::new(4, 3)).unwrap_err();
+        assert_eq!(
+            (err.op, err.expected, err.actual),
+            ("input", (4, 2), (4, 3))
+        );
+
+        let truth_in = Matrix::new(4, 2);
+        let err = nn.try_cost(&truth_in, &Matrix::new(3, 1)).unwrap_err();
+        assert_eq!((err.op, err.expected, err.actual), ("cost", (4, 1), (3, 1)));
+        assert_eq!(err.to_string(), "cost: expected shape 4x1, got 3x1");
+
+        let err = nn
+            .try_backprop(&mut grad, &Matrix::new(4, 1), &Matrix::new(4, 1))
+            .unwrap_err();
+        assert_eq!(
+            (err.op, err.expected, err.actual),
+            ("backprop", (4, 2), (4, 1))
+        );
+        let err = nn
+            .try_backprop(&mut grad, &truth_in, &Matrix::new(4, 2))
+            .unwrap_err();
+        assert_eq!(
+            (err.op, err.expected, err.actual),
+            ("backprop", (4, 1), (4, 2))
+        );
     }
 
-    pub fn fill(&mut self, value: f32) {
-        for i in 0..self.w.len() {
-            self.w[i].fill(value);
-            self.b[i].fill(value);
-            self.a[i].fill(value);
+    #[test]
+    fn test_matrix_f64_precision() {
+        let mut m: Matrix<f64> = Matrix::new(1, 2);
+        m.fill(1.);
+        m.add(&Matrix::from_2d_vec(&vec![vec![1e-10, 1e-10]]));
+        m.sub(&Matrix::from_2d_vec(&vec![vec![1., 1.]]));
+        assert!((m.get(0, 1) - 1e-10).abs() < 1e-15);
+
+        // in f64 a central difference agrees with backprop to ~1e-10
+        let truth_in = Matrix::from_2d_vec(&vec![vec![0., 1.], vec![1., 0.5]]);
+        let truth_out = Matrix::from_2d_vec(&vec![vec![1.], vec![0.]]);
+        let mut nn: NeuralNetwork<f64> = NeuralNetwork::new(&[2, 2, 1]);
+        let mut grad = NeuralNetwork::new(&[2, 2, 1]);
+        nn.rand(0., 1.);
+        nn.backprop(&mut grad, &truth_in, &truth_out);
+
+        let eps = 1e-5;
+        for index in 0..nn.value_mut(0, 0).data.len() {
+            let saved = nn.value_mut(0, 0).data[index];
+            nn.value_mut(0, 0).data[index] = saved + eps;
+            let plus = nn.cost(&truth_in, &truth_out);
+            nn.value_mut(0, 0).data[index] = saved - eps;
+            let minus = nn.cost(&truth_in, &truth_out);
+            nn.value_mut(0, 0).data[index] = saved;
+
+            let expected = (plus - minus) / (2. * eps);
+            let got = grad.value_mut(0, 0).data[index];
+            assert!((expected - got).abs() < 1e-9, "{} != {}", expected, got);
         }
-        let i = self.a.len() - 1;
-        self.a[i].fill(0.0);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
     fn test_nn_finite_diff_xor_gate() {
         const EPOCHS: usize = 20_000;
 
-        let truth_in = Matrix::from_2d_vec(&vec![
+        let truth_in: Matrix = Matrix::from_2d_vec(&vec![
             vec![0., 0.],
             vec![1., 0.],
             vec![0., 1.],
@@ -414,4 +655,218 @@ mod tests {
             assert!(cost < 0.01);
         }
     }
+
+    /// Compares every weight and bias gradient of `backprop` with central
+    /// differences of `cost`.
+    fn assert_gradients(
+        nn: &mut NeuralNetwork<f64>,
+        truth_in: &Matrix<f64>,
+        truth_out: &Matrix<f64>,
+        label: &str,
+    ) {
+        let layers = nn.model.layers();
+        let mut sizes = vec![layers[0].input_size()];
+        sizes.extend(layers.iter().map(|dense| dense.output_size()));
+        let mut grad = NeuralNetwork::new(&sizes);
+        nn.backprop(&mut grad, truth_in, truth_out);
+
+        let eps = 1e-6;
+        for l in 0..sizes.len() - 1 {
+            // weights, then biases
+            for p in 0..2 {
+                for index in 0..nn.value_mut(l, p).data.len() {
+                    let saved = nn.value_mut(l, p).data[index];
+                    nn.value_mut(l, p).data[index] = saved + eps;
+                    let plus = nn.cost(truth_in, truth_out);
+                    nn.value_mut(l, p).data[index] = saved - eps;
+                    let minus = nn.cost(truth_in, truth_out);
+                    nn.value_mut(l, p).data[index] = saved;
+
+                    let expected = (plus - minus) / (2. * eps);
+                    let got = grad.value_mut(l, p).data[index];
+                    assert!(
+                        (expected - got).abs() < 1e-6,
+                        "{}: {} != {}",
+                        label,
+                        expected,
+                        got
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_nn_activation_gradients() {
+        use Builtin::*;
+
+        let truth_in: Matrix<f64> = Matrix::from_fn(4, 2, |r, c| (r * 2 + c) as f64 / 4. - 0.8);
+        let truth_out = Matrix::from_fn(4, 2, |r, c| if r % 2 == c { 2. } else { -1. });
+
+        for activation in [
+            Sigmoid,
+            Tanh,
+            Relu,
+            LeakyRelu(0.1),
+            Elu(1.),
+            Linear,
+            Softmax,
+        ] {
+            let arch = [(2, Linear), (3, activation), (2, activation)];
+            let mut nn = NeuralNetwork::new(&arch);
+            nn.rand(-1., 1.);
+            assert_gradients(&mut nn, &truth_in, &truth_out, &format!("{:?}", activation));
+        }
+    }
+
+    #[test]
+    fn test_nn_loss_gradients() {
+        use crate::loss::{BinaryCrossEntropy, Huber, Mae, Mse, SoftmaxCrossEntropy};
+        use Builtin::*;
+
+        fn check(loss: impl Loss<f64> + 'static, output: Builtin) {
+            let truth_in = Matrix::from_fn(4, 2, |r, c| (r * 2 + c) as f64 / 4. - 0.8);
+            let truth_out = Matrix::from_fn(4, 2, |r, c| if r % 2 == c { 1. } else { 0. });
+
+            let name = loss.name();
+            let mut nn = NeuralNetwork::new(&[(2, Linear), (3, Tanh), (4, Relu), (2, output)]);
+            nn.rand(-1., 1.);
+            nn.set_loss(loss);
+            assert_gradients(&mut nn, &truth_in, &truth_out, &name);
+        }
+
+        check(Mse, Sigmoid);
+        check(Mae, Sigmoid);
+        check(Huber { delta: 0.2 }, Sigmoid);
+        check(BinaryCrossEntropy, Sigmoid);
+        // softmax cross-entropy takes logits
+        check(SoftmaxCrossEntropy, Linear);
+    }
+
+    #[test]
+    fn test_nn_regularization() {
+        use Builtin::*;
+
+        let truth_in: Matrix<f64> = Matrix::from_fn(4, 2, |r, c| (r * 2 + c) as f64 / 4. - 0.8);
+        let truth_out = Matrix::from_fn(4, 2, |r, c| if r % 2 == c { 1. } else { 0. });
+
+        let mut nn = NeuralNetwork::new(&[(2, Linear), (3, Tanh), (2, Sigmoid)]);
+        nn.rand(-1., 1.);
+        let cost = nn.cost(&truth_in, &truth_out);
+        assert_eq!(nn.penalty(), 0.);
+
+        // penalties count in both the cost and the gradients
+        let regularization = Regularization { l1: 0.01, l2: 0.1 };
+        nn.set_regularization(1, regularization);
+        let penalty = regularization.penalty(nn.model.layers()[0].w());
+        assert!(penalty > 0. && nn.penalty() == penalty);
+        assert!((nn.cost(&truth_in, &truth_out) - cost - penalty).abs() < 1e-12);
+        assert_gradients(&mut nn, &truth_in, &truth_out, "l1 and l2 on layer 1");
+
+        nn.set_regularization(2, Regularization { l1: 0.2, l2: 0. });
+        assert_gradients(&mut nn, &truth_in, &truth_out, "l1 on layer 2");
+    }
+
+    #[test]
+    fn test_nn_batched_backprop_matches_per_sample() {
+        let truth_in: Matrix<f64> = Matrix::from_fn(5, 3, |r, c| ((r * 3 + c) % 4) as f64 - 1.5);
+        let truth_out = Matrix::from_fn(5, 2, |r, c| ((r + c) % 2) as f64);
+
+        let arch = [
+            (3, Builtin::Linear),
+            (4, Builtin::Tanh),
+            (2, Builtin::Sigmoid),
+        ];
+        let mut nn = NeuralNetwork::new(&arch);
+        let mut batched = NeuralNetwork::new(&arch);
+        let mut single = NeuralNetwork::new(&arch);
+        nn.rand(-1., 1.);
+        nn.backprop(&mut batched, &truth_in, &truth_out);
+
+        fn parameters(nn: &NeuralNetwork<f64>) -> Vec<&Matrix<f64>> {
+            let layers = nn.model.layers().iter();
+            layers.flat_map(|dense| dense.parameters()).collect()
+        }
+
+        let mut sums: Vec<Matrix<f64>> = parameters(&batched).into_iter().cloned().collect();
+        sums.iter_mut().for_each(|m| m.fill(0.));
+        let mut cost = 0.;
+        for row in 0..truth_in.rows {
+            nn.backprop(&mut single, &truth_in.row(row), &truth_out.row(row));
+            for (sum, g) in sums.iter_mut().zip(parameters(&single)) {
+                sum.add(g);
+            }
+            cost += nn.cost(&truth_in.row(row), &truth_out.row(row));
+        }
+
+        let n = truth_in.rows as f64;
+        assert!((nn.cost(&truth_in, &truth_out) - cost / n).abs() < 1e-12);
+        for (sum, g) in sums.iter().zip(parameters(&batched)) {
+            for (x, y) in sum.data.iter().zip(&g.data) {
+                assert!((x / n - y).abs() < 1e-12, "{} != {}", x / n, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nn_custom_activation() {
+        use crate::activation::Swish;
+
+        #[derive(Debug)]
+        struct BadSquare;
+
+        impl<T: Float> Activation<T> for BadSquare {
+            fn forward(&self, x: T) -> T {
+                x * x
+            }
+
+            fn derivative(&self, x: T) -> T {
+                x
+            }
+        }
+
+        let mut nn: NeuralNetwork = NeuralNetwork::new(&[2, 3, 1]);
+        nn.rand(-1., 1.);
+        nn.set_activation(1, Swish);
+        assert!(nn.try_set_activation(2, BadSquare).is_err());
+        assert_eq!(nn.model.layers()[1].activation().name(), "sigmoid");
+
+        let input = Matrix::from_row_slice(1, 2, &[0.5, -0.5]);
+        nn.input(&input);
+        nn.forward();
+
+        let layers = nn.model.layers();
+        let mut hidden = Matrix::new(1, 3);
+        hidden.dot_into(&input, layers[0].w());
+        hidden.add(layers[0].b());
+        hidden.map_inplace(|x| x * sigmoid(x));
+        let mut z = Matrix::new(1, 1);
+        z.dot_into(&hidden, layers[1].w());
+        z.add(layers[1].b());
+        assert!((nn.output().get(0, 0) - sigmoid(z.get(0, 0))).abs() < 1e-6);
+        assert!(format!("{}", nn).contains("layer 1: 2 -> 3, Swish"));
+    }
+
+    #[test]
+    fn test_nn_learn_schedule() {
+        use crate::schedule::{ReduceOnPlateau, StepDecay};
+
+        let mut nn: NeuralNetwork<f64> = NeuralNetwork::new(&[1, 1]);
+        let mut grad = NeuralNetwork::new(&[1, 1]);
+        grad.fill(1.);
+        assert_eq!(nn.learn(&mut grad), LEARN_RATE as f64);
+
+        nn.set_schedule(StepDecay::new(1., 0.5, 2));
+        let rates: Vec<f64> = (0..4).map(|_| nn.learn(&mut grad)).collect();
+        assert_eq!(rates, vec![1., 1., 0.5, 0.5]);
+        assert_eq!(
+            nn.model.layers()[0].w().data,
+            vec![-(LEARN_RATE as f64) - 3.]
+        );
+
+        nn.set_schedule(ReduceOnPlateau::new(0.1, 0.5, 0));
+        nn.end_epoch(1.);
+        nn.end_epoch(1.);
+        assert_eq!(nn.learn(&mut grad), 0.05);
+    }
 }
@@ -1,9 +1,10 @@
 use super::emit;
-use crate::data::EPOCHS_PER_PRINT;
-use crate::data::{EPOCHS, LEARN_RATE, RNG_SEED, STEP};
 use rand::rngs::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
+use tresh::data::EPOCHS_PER_PRINT;
+use tresh::data::{EPOCHS, LEARN_RATE, RNG_SEED, STEP};
+use tresh::neural_network::Matrix;
 
 const TRAINING_DATA: [[f32; 2]; 5] = [
     [0.0, 0.0], //
@@ -61,4 +62,12 @@ pub fn run(window: &tauri::Window) {
 
     emit(&window, "<hr>");
     emit(&window, format!("w {}", w));
+
+    // Exact least squares fit to compare gradient descent against
+    let x = Matrix::from_fn(TRAINING_DATA.len(), 1, |i, _| TRAINING_DATA[i][0]);
+    let y = Matrix::from_fn(TRAINING_DATA.len(), 1, |i, _| TRAINING_DATA[i][1]);
+    match x.try_least_squares(&y) {
+        Ok(exact) => emit(window, format!("exact w {}", exact[(0, 0)])),
+        Err(err) => emit(window, format!("Error: {}", err)),
+    }
 }
@@ -1,9 +1,9 @@
 use super::emit;
-use crate::data::{EPOCHS, EPOCHS_PER_PRINT, LEARN_RATE, STEP, TRAINING_DATA};
-use crate::neural_network::sigmoid;
 use rand::rngs::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
+use tresh::data::{EPOCHS, EPOCHS_PER_PRINT, LEARN_RATE, STEP, TRAINING_DATA};
+use tresh::neural_network::sigmoid;
 
 fn cost(w1: f32, w2: f32, b: f32) -> f32 {
     let mut result = 0.0;
@@ -1,8 +1,8 @@
 use super::emit;
-use crate::neural_network::sigmoid;
-use crate::data::{EPOCHS, EPOCHS_PER_PRINT, LEARN_RATE, STEP, TRAINING_DATA};
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
+use tresh::data::{EPOCHS, EPOCHS_PER_PRINT, LEARN_RATE, STEP, TRAINING_DATA};
+use tresh::neural_network::sigmoid;
 
 #[derive(Debug)]
 struct XorGate {
@@ -1,7 +1,8 @@
-use crate::{
+use super::emit;
+use rand::{rngs::StdRng, SeedableRng};
+use tresh::{
+    data::{EPOCHS, EPOCHS_PER_PRINT, LEARN_RATE, RNG_SEED, STEP},
     neural_network::Matrix,
-    data::{EPOCHS, EPOCHS_PER_PRINT, LEARN_RATE, STEP},
-    emit,
 };
 
 #[derive(Debug)]
@@ -17,31 +18,15 @@ struct Xor {
     a2: Matrix,
 }
 
-pub fn mat_dot(dst: &mut Matrix, a: &Matrix, b: &Matrix) {
-    assert_eq!(a.columns, b.rows);
-    assert_eq!(dst.rows, a.rows);
-    assert_eq!(dst.columns, b.columns);
-
-    for row in 0..dst.rows {
-        for col in 0..dst.columns {
-            let mut sum = 0.0;
-            for k in 0..a.columns {
-                sum += a.get(row, k) * b.get(k, col);
-            }
-            dst.set(row, col, sum);
-        }
-    }
-}
-
 impl Xor {
     pub fn forward(&mut self) {
         // First layer forward pass
-        mat_dot(&mut self.a1, &self.a0, &self.w1);
+        self.a1.dot_into(&self.a0, &self.w1);
         self.a1.add(&self.b1);
         self.a1.sigmoid();
 
         // Second layer forward pass
-        mat_dot(&mut self.a2, &self.a1, &self.w2);
+        self.a2.dot_into(&self.a1, &self.w2);
         self.a2.add(&self.b2);
         self.a2.sigmoid();
     }
@@ -56,11 +41,11 @@ impl Xor {
             let truth_in = ti.row(row);
             let truth_out = to.row(row);
 
-            self.a0 = truth_in;
+            self.a0.copy_from(&truth_in);
             self.forward();
 
             for col in 0..to.columns {
-                let d = self.a2.get(0, col) - truth_out.get(0, col);
+                let d = self.a2[(0, col)] - truth_out[(0, col)];
                 cost += d * d;
             }
         }
@@ -76,69 +61,46 @@ fn finite_difference(m: &mut Xor, g: &mut Xor, ti: &Matrix, to: &Matrix) {
 
     for i in 0..m.w1.rows {
         for j in 0..m.w1.columns {
-            saved = m.w1.get(i, j);
-            m.w1.set(i, j, saved + STEP);
-            let c2 = m.cost(ti, to);
-            g.w1.set(i, j, (c2 - c) / STEP);
-            m.w1.set(i, j, saved);
+            saved = m.w1[(i, j)];
+            m.w1[(i, j)] = saved + STEP;
+            g.w1[(i, j)] = (m.cost(ti, to) - c) / STEP;
+            m.w1[(i, j)] = saved;
         }
     }
 
     for i in 0..m.b1.rows {
         for j in 0..m.b1.columns {
-            saved = m.b1.get(i, j);
-            m.b1.set(i, j, saved + STEP);
-            let c2 = m.cost(ti, to);
-            g.b1.set(i, j, (c2 - c) / STEP);
-            m.b1.set(i, j, saved);
+            saved = m.b1[(i, j)];
+            m.b1[(i, j)] = saved + STEP;
+            g.b1[(i, j)] = (m.cost(ti, to) - c) / STEP;
+            m.b1[(i, j)] = saved;
         }
     }
 
     for i in 0..m.w2.rows {
         for j in 0..m.w2.columns {
-            saved = m.w2.get(i, j);
-            m.w2.set(i, j, saved + STEP);
-            let c2 = m.cost(ti, to);
-            g.w2.set(i, j, (c2 - c) / STEP);
-            m.w2.set(i, j, saved);
+            saved = m.w2[(i, j)];
+            m.w2[(i, j)] = saved + STEP;
+            g.w2[(i, j)] = (m.cost(ti, to) - c) / STEP;
+            m.w2[(i, j)] = saved;
         }
     }
 
     for i in 0..m.b2.rows {
         for j in 0..m.b2.columns {
-            saved = m.b2.get(i, j);
-            m.b2.set(i, j, saved + STEP);
-            let c2 = m.cost(ti, to);
-            g.b2.set(i, j, (c2 - c) / STEP);
-            m.b2.set(i, j, saved);
+            saved = m.b2[(i, j)];
+            m.b2[(i, j)] = saved + STEP;
+            g.b2[(i, j)] = (m.cost(ti, to) - c) / STEP;
+            m.b2[(i, j)] = saved;
         }
     }
 }
 
-fn xor_learn(m: &mut Xor, g: &mut Xor) {
-    for i in 0..m.w1.rows {
-        for j in 0..m.w1.columns {
-            m.w1.set(i, j, m.w1.get(i, j) - g.w1.get(i, j) * LEARN_RATE);
-        }
-    }
-
-    for i in 0..m.b1.rows {
-        for j in 0..m.b1.columns {
-            m.b1.set(i, j, m.b1.get(i, j) - g.b1.get(i, j) * LEARN_RATE);
-        }
-    }
-
-    for i in 0..m.w2.rows {
-        for j in 0..m.w2.columns {
-            m.w2.set(i, j, m.w2.get(i, j) - g.w2.get(i, j) * LEARN_RATE);
-        }
-    }
-
-    for i in 0..m.b2.rows {
-        for j in 0..m.b2.columns {
-            m.b2.set(i, j, m.b2.get(i, j) - g.b2.get(i, j) * LEARN_RATE);
-        }
-    }
+fn xor_learn(m: &mut Xor, g: &Xor) {
+    m.w1 -= &g.w1 * LEARN_RATE;
+    m.b1 -= &g.b1 * LEARN_RATE;
+    m.w2 -= &g.w2 * LEARN_RATE;
+    m.b2 -= &g.b2 * LEARN_RATE;
 }
 
 pub fn run(window: &tauri::Window) {
@@ -151,18 +113,10 @@ pub fn run(window: &tauri::Window) {
     ];
 
     // Input (from truth table)
-    let mut ti = Matrix::new(4, 2);
-    for i in 0..4 {
-        for j in 0..2 {
-            ti.set(i, j, td[i][j]);
-        }
-    }
+    let ti = Matrix::from_fn(4, 2, |i, j| td[i][j]);
 
     // Target (from truth table)
-    let mut to = Matrix::new(4, 1);
-    for i in 0..4 {
-        to.set(i, 0, td[i][2]);
-    }
+    let to = Matrix::from_fn(4, 1, |i, _| td[i][2]);
 
     // Network
     let mut m = Xor {
@@ -184,11 +138,12 @@ pub fn run(window: &tauri::Window) {
         a2: Matrix::new(1, 1),
     };
 
-    m.w1.fill_rand(0.0, 1.0);
-    m.b1.fill_rand(0.0, 1.0);
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    m.w1.fill_uniform(&mut rng, 0.0, 1.0);
+    m.b1.fill_uniform(&mut rng, 0.0, 1.0);
 
-    m.w2.fill_rand(0.0, 1.0);
-    m.b2.fill_rand(0.0, 1.0);
+    m.w2.fill_uniform(&mut rng, 0.0, 1.0);
+    m.b2.fill_uniform(&mut rng, 0.0, 1.0);
 
     // Print cost
     let c = m.cost(&ti, &to);
@@ -196,7 +151,7 @@ pub fn run(window: &tauri::Window) {
 
     for epoch in 1..=EPOCHS {
         finite_difference(&mut m, &mut g, &ti, &to);
-        xor_learn(&mut m, &mut g);
+        xor_learn(&mut m, &g);
         if epoch % EPOCHS_PER_PRINT == 0 {
             let cost = m.cost(&ti, &to);
             emit(window, format!("Cost after epoch {}: {}", epoch, cost));
@@ -213,12 +168,12 @@ pub fn run(window: &tauri::Window) {
     // Validate
     for i in 0..2 {
         for j in 0..2 {
-            m.a0.set(0, 0, i as f32);
-            m.a0.set(0, 1, j as f32);
+            m.a0[(0, 0)] = i as f32;
+            m.a0[(0, 1)] = j as f32;
 
             m.forward();
 
-            let y = m.a2.get(0, 0);
+            let y = m.a2[(0, 0)];
 
             emit(window, format!("{} XOR {} = {}", i, j, y));
         }
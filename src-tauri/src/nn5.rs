@@ -1,25 +1,28 @@
-use crate::{
+use super::emit;
+use tresh::{
     data::{EPOCHS, EPOCHS_PER_PRINT},
-    emit,
+    display::MatrixFormat,
     neural_network::{Matrix, NeuralNetwork},
 };
 
 const USE_FINITE_DIFF: bool = false;
 
 pub fn run(window: &tauri::Window) {
+    let format = MatrixFormat::default();
+
     emit(window, "settings");
     emit(window, "<hr>");
     emit(window, format!("USE_FINITE_DIFF: {}", USE_FINITE_DIFF));
     emit(window, format!("EPOCHS: {}", EPOCHS));
     emit(window, "<hr>");
 
-    let truth_in = Matrix::from_2d_vec(&vec![
+    let truth_in: Matrix = Matrix::from_2d_vec(&vec![
         vec![0., 0.],
         vec![1., 0.],
         vec![0., 1.],
         vec![1., 1.],
     ]);
-    let truth_out = Matrix::from_2d_vec(&vec![
+    let truth_out: Matrix = Matrix::from_2d_vec(&vec![
         vec![0.], //
         vec![1.], //
         vec![1.], //
@@ -31,24 +34,35 @@ pub fn run(window: &tauri::Window) {
     let mut grad = NeuralNetwork::new(&arch);
 
     nn.rand(0., 1.);
-    let cost_init = nn.cost(&truth_in, &truth_out);
+    let cost_init = match nn.try_cost(&truth_in, &truth_out) {
+        Ok(cost) => cost,
+        Err(err) => {
+            emit(window, format!("Error: {}", err));
+            return;
+        }
+    };
     emit(window, format!("Cost pre-training: {}", cost_init));
 
-    emit(window, format!("grad pre-training: {}", grad.to_string()));
+    emit(window, "grad pre-training:");
+    emit(window, grad.to_html(&format));
     for epoch in 1..=EPOCHS {
         if USE_FINITE_DIFF {
             nn.finite_diff(&mut grad, &truth_in, &truth_out);
         } else {
             nn.backprop(&mut grad, &truth_in, &truth_out);
         }
-        nn.learn(&mut grad);
+        let rate = nn.learn(&mut grad);
         if epoch % EPOCHS_PER_PRINT == 0 {
             let new_cost = nn.cost(&truth_in, &truth_out);
-            emit(window, format!("Cost epoch {}: {}", epoch, new_cost));
+            emit(
+                window,
+                format!("Cost epoch {}: {} (rate {})", epoch, new_cost, rate),
+            );
         }
     }
 
-    emit(window, format!("grad post-training: {}", grad.to_string()));
+    emit(window, "grad post-training:");
+    emit(window, grad.to_html(&format));
 
     emit(window, "<hr>");
     emit(window, "validation");
@@ -77,7 +91,8 @@ pub fn run(window: &tauri::Window) {
     //     );
     // }
 
-    // emit(window, "<hr>");
-
-    // emit(window, nn.to_string());
+    emit(window, "<hr>");
+    emit(window, "weights");
+    emit(window, "<hr>");
+    emit(window, nn.to_html(&format));
 }
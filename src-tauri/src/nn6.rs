@@ -1 +1,97 @@
-pub fn run(window: &tauri::Window) {}
+use super::emit;
+use tresh::{
+    activation::Builtin::{Linear, Sigmoid},
+    data::{EPOCHS, EPOCHS_PER_PRINT},
+    loss::BinaryCrossEntropy,
+    neural_network::Matrix,
+    optimizer::Adam,
+    schedule::{CosineRestarts, Warmup},
+    sequential::Sequential,
+    training::{Progress, TrainConfig},
+};
+
+// Adds two BITS wide numbers, the output has one more bit for the carry
+const BITS: usize = 4;
+const BATCH_SIZE: usize = 32;
+const LEARN_RATE: f64 = 1e-2;
+const WARMUP_STEPS: usize = 50;
+
+/// Value of a row of little-endian bits.
+fn from_bits(row: impl Iterator<Item = f32>) -> usize {
+    row.enumerate()
+        .map(|(bit, x)| if x > 0.5 { 1 << bit } else { 0 })
+        .sum()
+}
+
+pub fn run(window: &tauri::Window) {
+    let n = 1 << BITS;
+
+    // row x * n + y holds the bits of x then the bits of y
+    let truth_in: Matrix = Matrix::from_fn(n * n, 2 * BITS, |row, col| {
+        let value = if col < BITS { row / n } else { row % n };
+        ((value >> (col % BITS)) & 1) as f32
+    });
+    let truth_out: Matrix = Matrix::from_fn(n * n, BITS + 1, |row, col| {
+        (((row / n + row % n) >> col) & 1) as f32
+    });
+
+    let config = TrainConfig {
+        batch_size: BATCH_SIZE,
+        ..TrainConfig::default()
+    };
+
+    emit(window, "settings");
+    emit(window, "<hr>");
+    emit(window, format!("BITS: {}", BITS));
+    emit(window, format!("EPOCHS: {}", EPOCHS));
+    emit(window, format!("BATCH_SIZE: {}", BATCH_SIZE));
+    emit(window, "<hr>");
+
+    let mut nn =
+        Sequential::from_arch(&[(2 * BITS, Linear), (4 * BITS, Sigmoid), (BITS + 1, Sigmoid)]);
+    nn.set_loss(BinaryCrossEntropy);
+    nn.rand(-1., 1.);
+
+    // restart the cosine every EPOCHS_PER_PRINT epochs
+    let batches = truth_in.rows.div_ceil(BATCH_SIZE);
+    let mut schedule = Warmup {
+        steps: WARMUP_STEPS,
+        inner: CosineRestarts::new(LEARN_RATE, LEARN_RATE / 10., EPOCHS_PER_PRINT * batches, 1),
+    };
+
+    let mut optimizer = Adam::new(LEARN_RATE);
+    nn.fit(
+        &truth_in,
+        &truth_out,
+        &mut optimizer,
+        &mut schedule,
+        &config,
+        |progress| {
+            if let Progress::Epoch { epoch, loss, rate } = progress {
+                if epoch % EPOCHS_PER_PRINT == 0 {
+                    emit(
+                        window,
+                        format!("Cost epoch {}: {} (rate {:.5})", epoch, loss, rate),
+                    );
+                }
+            }
+        },
+    );
+
+    emit(window, "<hr>");
+    emit(window, "validation");
+    emit(window, "<hr>");
+
+    nn.input(&truth_in);
+    nn.forward();
+    let mut wrong = 0;
+    for row in 0..truth_in.rows {
+        let (x, y) = (row / n, row % n);
+        let z = from_bits(nn.output().row(row).iter());
+        if z != x + y {
+            wrong += 1;
+            emit(window, format!("{} + {} = {}", x, y, z));
+        }
+    }
+    emit(window, format!("{} of {} sums wrong", wrong, truth_in.rows));
+}
@@ -0,0 +1,329 @@
+//! Update rules turning the gradients from `backprop` into parameter steps.
+
+use crate::float::Float;
+use crate::layer::Parameter;
+use crate::neural_network::Matrix;
+
+/// Keeps whatever state it needs per parameter, so `step` has to be given
+/// the parameters of the same model, in the same order, on every call.
+pub trait Optimizer<T: Float>: std::fmt::Debug + Send {
+    fn step(&mut self, parameters: &mut [Parameter<'_, T>]);
+
+    fn learning_rate(&self) -> f64;
+
+    fn set_learning_rate(&mut self, rate: f64);
+}
+
+/// Zeroed state matching the parameter shapes, reset when they change.
+/// Returns whether it was reset.
+fn init_state<T: Float>(state: &mut Vec<Matrix<T>>, parameters: &[Parameter<'_, T>]) -> bool {
+    let matches = state.len() == parameters.len()
+        && state
+            .iter()
+            .zip(parameters)
+            .all(|(s, p)| s.shape() == p.value.shape());
+    if !matches {
+        *state = parameters
+            .iter()
+            .map(|p| Matrix::new(p.value.rows, p.value.columns))
+            .collect();
+    }
+    !matches
+}
+
+/// Gradient descent, optionally with classic or Nesterov momentum.
+#[derive(Debug, Clone)]
+pub struct Sgd<T = f32> {
+    pub rate: f64,
+    pub momentum: f64,
+    pub nesterov: bool,
+    velocity: Vec<Matrix<T>>,
+}
+
+impl<T: Float> Sgd<T> {
+    pub fn new(rate: f64) -> Sgd<T> {
+        Sgd::with_momentum(rate, 0.)
+    }
+
+    /// `v = momentum * v + grad`, then steps by `rate * v`.
+    pub fn with_momentum(rate: f64, momentum: f64) -> Sgd<T> {
+        Sgd {
+            rate,
+            momentum,
+            nesterov: false,
+            velocity: Vec::new(),
+        }
+    }
+
+    /// Like `with_momentum` but steps by `rate * (grad + momentum * v)`,
+    /// looking ahead along the velocity.
+    pub fn nesterov(rate: f64, momentum: f64) -> Sgd<T> {
+        Sgd {
+            nesterov: true,
+            ..Sgd::with_momentum(rate, momentum)
+        }
+    }
+}
+
+impl<T: Float> Optimizer<T> for Sgd<T> {
+    fn step(&mut self, parameters: &mut [Parameter<'_, T>]) {
+        let rate = T::from_f64(self.rate);
+        let momentum = T::from_f64(self.momentum);
+
+        if self.momentum == 0. {
+            for p in parameters.iter_mut() {
+                p.value.zip_with_inplace(&*p.grad, |x, g| x - rate * g);
+            }
+            return;
+        }
+
+        init_state(&mut self.velocity, parameters);
+        for (p, v) in parameters.iter_mut().zip(&mut self.velocity) {
+            for ((x, &g), v) in p.value.data.iter_mut().zip(&p.grad.data).zip(&mut v.data) {
+                *v = momentum * *v + g;
+                let update = if self.nesterov { g + momentum * *v } else { *v };
+                *x -= rate * update;
+            }
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.rate
+    }
+
+    fn set_learning_rate(&mut self, rate: f64) {
+        self.rate = rate;
+    }
+}
+
+/// Divides each gradient by a running root mean square of its past values.
+#[derive(Debug, Clone)]
+pub struct RmsProp<T = f32> {
+    pub rate: f64,
+    /// Weight of the old mean square in the running average.
+    pub decay: f64,
+    pub epsilon: f64,
+    mean_square: Vec<Matrix<T>>,
+}
+
+impl<T: Float> RmsProp<T> {
+    pub fn new(rate: f64) -> RmsProp<T> {
+        RmsProp {
+            rate,
+            decay: 0.9,
+            epsilon: 1e-8,
+            mean_square: Vec::new(),
+        }
+    }
+}
+
+impl<T: Float> Optimizer<T> for RmsProp<T> {
+    fn step(&mut self, parameters: &mut [Parameter<'_, T>]) {
+        let rate = T::from_f64(self.rate);
+        let decay = T::from_f64(self.decay);
+        let epsilon = T::from_f64(self.epsilon);
+
+        init_state(&mut self.mean_square, parameters);
+        for (p, s) in parameters.iter_mut().zip(&mut self.mean_square) {
+            for ((x, &g), s) in p.value.data.iter_mut().zip(&p.grad.data).zip(&mut s.data) {
+                *s = decay * *s + (T::ONE - decay) * g * g;
+                *x -= rate * g / (s.sqrt() + epsilon);
+            }
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.rate
+    }
+
+    fn set_learning_rate(&mut self, rate: f64) {
+        self.rate = rate;
+    }
+}
+
+/// Running averages of the gradient and its square, bias corrected for
+/// the first steps.
+#[derive(Debug, Clone)]
+pub struct Adam<T = f32> {
+    pub rate: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+    /// `beta1^t` and `beta2^t` after `t` steps, for the bias corrections.
+    beta1_power: f64,
+    beta2_power: f64,
+    first_moment: Vec<Matrix<T>>,
+    second_moment: Vec<Matrix<T>>,
+}
+
+impl<T: Float> Adam<T> {
+    pub fn new(rate: f64) -> Adam<T> {
+        Adam {
+            rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            beta1_power: 1.,
+            beta2_power: 1.,
+            first_moment: Vec::new(),
+            second_moment: Vec::new(),
+        }
+    }
+}
+
+impl<T: Float> Optimizer<T> for Adam<T> {
+    fn step(&mut self, parameters: &mut [Parameter<'_, T>]) {
+        if init_state(&mut self.first_moment, parameters) {
+            init_state(&mut self.second_moment, parameters);
+            self.beta1_power = 1.;
+            self.beta2_power = 1.;
+        }
+        self.beta1_power *= self.beta1;
+        self.beta2_power *= self.beta2;
+
+        let (beta1, beta2) = (T::from_f64(self.beta1), T::from_f64(self.beta2));
+        // bias corrections folded into the rate
+        let correction1 = 1. - self.beta1_power;
+        let correction2 = (1. - self.beta2_power).sqrt();
+        let rate = T::from_f64(self.rate * correction2 / correction1);
+        let epsilon = T::from_f64(self.epsilon * correction2);
+
+        let moments = self.first_moment.iter_mut().zip(&mut self.second_moment);
+        for (p, (m, v)) in parameters.iter_mut().zip(moments) {
+            let state = m.data.iter_mut().zip(&mut v.data);
+            for ((x, &g), (m, v)) in p.value.data.iter_mut().zip(&p.grad.data).zip(state) {
+                *m = beta1 * *m + (T::ONE - beta1) * g;
+                *v = beta2 * *v + (T::ONE - beta2) * g * g;
+                *x -= rate * *m / (v.sqrt() + epsilon);
+            }
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.rate
+    }
+
+    fn set_learning_rate(&mut self, rate: f64) {
+        self.rate = rate;
+    }
+}
+
+/// Adam with decoupled weight decay, parameters shrink by
+/// `rate * weight_decay` of themselves each step whatever their gradient.
+/// Parameters without `decay`, the biases, are left to Adam alone.
+#[derive(Debug, Clone)]
+pub struct AdamW<T = f32> {
+    pub adam: Adam<T>,
+    pub weight_decay: f64,
+}
+
+impl<T: Float> AdamW<T> {
+    pub fn new(rate: f64, weight_decay: f64) -> AdamW<T> {
+        AdamW {
+            adam: Adam::new(rate),
+            weight_decay,
+        }
+    }
+}
+
+impl<T: Float> Optimizer<T> for AdamW<T> {
+    fn step(&mut self, parameters: &mut [Parameter<'_, T>]) {
+        let keep = T::ONE - T::from_f64(self.adam.rate * self.weight_decay);
+        for p in parameters.iter_mut().filter(|p| p.decay) {
+            p.value.dotf(keep);
+        }
+        self.adam.step(parameters);
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.adam.rate
+    }
+
+    fn set_learning_rate(&mut self, rate: f64) {
+        self.adam.rate = rate;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Steps of `optimizer` on `|x - target|²`, starting from zero.
+    fn minimize(optimizer: &mut dyn Optimizer<f64>, steps: usize) -> Matrix<f64> {
+        let target: Matrix<f64> = Matrix::from_rows([[1., -2., 3.]]);
+        let mut x = Matrix::new(1, 3);
+        let mut grad = Matrix::new(1, 3);
+        for _ in 0..steps {
+            grad.copy_from(&x);
+            grad.sub(&target);
+            grad.dotf(2.);
+            optimizer.step(&mut [Parameter {
+                value: &mut x,
+                grad: &mut grad,
+                decay: true,
+            }]);
+        }
+        x.sub(&target);
+        x
+    }
+
+    #[test]
+    fn test_optimizers_converge() {
+        let optimizers: [Box<dyn Optimizer<f64>>; 6] = [
+            Box::new(Sgd::new(0.1)),
+            Box::new(Sgd::with_momentum(0.05, 0.9)),
+            Box::new(Sgd::nesterov(0.05, 0.9)),
+            Box::new(RmsProp::new(0.01)),
+            Box::new(Adam::new(0.1)),
+            Box::new(AdamW::new(0.1, 0.)),
+        ];
+        for mut optimizer in optimizers {
+            let error = minimize(optimizer.as_mut(), 500);
+            assert!(
+                error.data.iter().all(|e| e.abs() < 1e-2),
+                "{:?}: {}",
+                optimizer,
+                error
+            );
+        }
+
+        // the first Adam step moves every coordinate by about the rate
+        let error = minimize(&mut Adam::new(0.1), 1);
+        assert_eq!(
+            error
+                .data
+                .iter()
+                .map(|e| (e * 1e6).round() / 1e6)
+                .collect::<Vec<_>>(),
+            vec![-0.9, 1.9, -2.9]
+        );
+
+        // with a flat gradient AdamW only decays, and leaves biases alone
+        let mut x: Matrix<f64> = Matrix::from_rows([[2.]]);
+        let mut b = x.clone();
+        let (mut grad, mut grad_b) = (Matrix::new(1, 1), Matrix::new(1, 1));
+        AdamW::new(0.1, 0.5).step(&mut [
+            Parameter {
+                value: &mut x,
+                grad: &mut grad,
+                decay: true,
+            },
+            Parameter {
+                value: &mut b,
+                grad: &mut grad_b,
+                decay: false,
+            },
+        ]);
+        assert!((x.data[0] - 1.9).abs() < 1e-12);
+        assert_eq!(b.data, vec![2.]);
+
+        // long after the powers underflow, as past i32::MAX steps, the
+        // corrections are 1 and steps stay finite
+        let mut adam = Adam::new(0.1);
+        minimize(&mut adam, 1);
+        adam.beta1_power = 0.;
+        adam.beta2_power = 0.;
+        assert!(minimize(&mut adam, 1).data.iter().all(|e| e.is_finite()));
+        assert!(adam.beta1_power == 0. && adam.beta2_power == 0.);
+    }
+}
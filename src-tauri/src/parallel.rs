@@ -0,0 +1,135 @@
+//! Row-block threading for large matrix operations, enabled by the `parallel` feature.
+
+use crate::float::Float;
+use crate::matrix_view::MatrixViewMut;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 0 means one thread per available core.
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+/// Operations doing less work than this (multiply-adds or elements) stay serial.
+static THRESHOLD: AtomicUsize = AtomicUsize::new(1 << 20);
+
+pub fn set_threads(threads: usize) {
+    THREADS.store(threads, Ordering::Relaxed);
+}
+
+pub fn threads() -> usize {
+    Threading::current().threads()
+}
+
+pub fn set_threshold(work: usize) {
+    THRESHOLD.store(work, Ordering::Relaxed);
+}
+
+pub fn threshold() -> usize {
+    THRESHOLD.load(Ordering::Relaxed)
+}
+
+pub(crate) fn worth_splitting(work: usize) -> bool {
+    Threading::current().worth_splitting(work)
+}
+
+/// Thread count and threshold for one operation, `current` reads the
+/// process-wide settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threading {
+    /// 0 means one thread per available core.
+    pub threads: usize,
+    pub threshold: usize,
+}
+
+impl Threading {
+    /// The values of `set_threads` and `set_threshold`.
+    pub fn current() -> Threading {
+        Threading {
+            threads: THREADS.load(Ordering::Relaxed),
+            threshold: threshold(),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        match self.threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            threads => threads,
+        }
+    }
+
+    pub(crate) fn worth_splitting(&self, work: usize) -> bool {
+        work >= self.threshold && self.threads() > 1
+    }
+}
+
+/// Splits `view` into one block of rows per thread and calls `f` with the
+/// first row index of every block, returns once every block is done.
+pub(crate) fn for_row_blocks<T: Float>(
+    threads: usize,
+    view: MatrixViewMut<T>,
+    f: impl Fn(usize, MatrixViewMut<T>) + Sync,
+) {
+    let threads = threads.min(view.rows).max(1);
+    let rows_per_block = view.rows.div_ceil(threads);
+
+    std::thread::scope(|scope| {
+        let f = &f;
+        let mut rest = view;
+        let mut row = 0;
+
+        while rest.rows > rows_per_block {
+            let (block, tail) = rest.split_rows_mut(rows_per_block);
+            scope.spawn(move || f(row, block));
+            row += rows_per_block;
+            rest = tail;
+        }
+
+        // the calling thread takes the last block
+        f(row, rest);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::float::Cpu;
+    use crate::matmul::{gemm_serial, gemm_threaded};
+    use crate::matrix_view::AsMatrixView;
+    use crate::neural_network::{sigmoid, Matrix};
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let mut a: Matrix = Matrix::new(97, 61);
+        let mut b: Matrix = Matrix::new(61, 83);
+        for (i, value) in a.data.iter_mut().enumerate() {
+            *value = (i % 13) as f32 - 6.;
+        }
+        for (i, value) in b.data.iter_mut().enumerate() {
+            *value = (i % 7) as f32 - 3.;
+        }
+
+        let mut serial = Matrix::new(97, 83);
+        gemm_serial(&mut serial.view_mut(), &a.view(), &b.view(), Cpu::detect());
+
+        let threading = Threading {
+            threads: 4,
+            threshold: 0,
+        };
+        let mut threaded = Matrix::new(97, 83);
+        gemm_threaded(&mut threaded.view_mut(), &a.view(), &b.view(), threading);
+        assert_eq!(serial.data, threaded.data);
+
+        // one block per thread, each sees its own first row
+        let mut rows = Matrix::new(97, 83);
+        for_row_blocks(4, rows.view_mut(), |row, mut block| {
+            block.map_inplace(|_| row as f32);
+        });
+        let starts: Vec<f32> = (0..97).map(|row| rows.get(row, 0)).collect();
+        assert_eq!(starts[..25], [0.; 25]);
+        assert_eq!(starts[25..50], [25.; 25]);
+        assert_eq!(starts[96], 75.);
+
+        serial.map_inplace(sigmoid);
+        for_row_blocks(4, threaded.view_mut(), |_, mut block| {
+            block.map_inplace(sigmoid)
+        });
+        assert_eq!(serial.data, threaded.data);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::float::Float;
+use crate::neural_network::Matrix;
+use rand::Rng;
+use std::f64::consts::TAU;
+
+/// Standard normal sample, Box-Muller transform.
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    // 1 - [0, 1) keeps ln away from 0
+    let u1 = 1. - rng.gen::<f64>();
+    let u2 = rng.gen::<f64>();
+    (-2. * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+/// Random initialization, every fill draws from the caller's `rng` so a
+/// whole network can be reproduced from a single seed.
+impl<T: Float> Matrix<T> {
+    /// Uniform in `low..high`.
+    pub fn fill_uniform(&mut self, rng: &mut impl Rng, low: T, high: T) {
+        for value in self.data.iter_mut() {
+            *value = rng.gen_range(low..high);
+        }
+    }
+
+    pub fn fill_normal(&mut self, rng: &mut impl Rng, mean: T, std_dev: T) {
+        for value in self.data.iter_mut() {
+            *value = mean + std_dev * T::from_f64(standard_normal(rng));
+        }
+    }
+
+    /// Normal with samples further than two standard deviations from the
+    /// mean drawn again.
+    pub fn fill_truncated_normal(&mut self, rng: &mut impl Rng, mean: T, std_dev: T) {
+        for value in self.data.iter_mut() {
+            let z = loop {
+                let z = standard_normal(rng);
+                if z.abs() <= 2. {
+                    break z;
+                }
+            };
+            *value = mean + std_dev * T::from_f64(z);
+        }
+    }
+
+    /// 1 with probability `p`, 0 otherwise.
+    pub fn fill_bernoulli(&mut self, rng: &mut impl Rng, p: f64) {
+        assert!((0. ..=1.).contains(&p), "bernoulli p must be in 0..=1");
+
+        for value in self.data.iter_mut() {
+            *value = if rng.gen_bool(p) { T::ONE } else { T::ZERO };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reductions::Reduce;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_fill_distributions() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut m: Matrix<f64> = Matrix::new(100, 100);
+
+        m.fill_normal(&mut rng, 3., 2.);
+        let var = m.map(|x| (x - 3.) * (x - 3.)).mean();
+        assert!((m.mean() - 3.).abs() < 0.1);
+        assert!((var.sqrt() - 2.).abs() < 0.1);
+
+        m.fill_truncated_normal(&mut rng, 0., 1.);
+        assert!(m.max().unwrap() <= 2. && m.min().unwrap() >= -2.);
+
+        m.fill_uniform(&mut rng, -1., 1.);
+        assert!(m.max().unwrap() < 1. && m.min().unwrap() >= -1.);
+
+        m.fill_bernoulli(&mut rng, 0.25);
+        assert!(m.data.iter().all(|&x| x == 0. || x == 1.));
+        assert!((m.mean() - 0.25).abs() < 0.05);
+
+        // consecutive fills keep drawing from the same stream
+        let mut a: Matrix = Matrix::new(2, 2);
+        let mut b: Matrix = Matrix::new(2, 2);
+        a.fill_uniform(&mut rng, 0., 1.);
+        b.fill_uniform(&mut rng, 0., 1.);
+        assert_ne!(a.data, b.data);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::float::Float;
+use crate::matrix_view::{AsMatrixView, MatrixView};
+use crate::neural_network::Matrix;
+
+/// Position and value of the largest element, first one wins on ties.
+fn argmax_of<T: Float>(values: impl Iterator<Item = T>) -> Option<(usize, T)> {
+    values
+        .enumerate()
+        .fold(None, |best, (i, value)| match best {
+            Some((_, max)) if value > max => Some((i, value)),
+            None => Some((i, value)),
+            _ => best,
+        })
+}
+
+fn argmin_of<T: Float>(values: impl Iterator<Item = T>) -> Option<(usize, T)> {
+    argmax_of(values.map(|value| -value)).map(|(i, value)| (i, -value))
+}
+
+/// Whole-matrix and per-row/per-column reductions, available on
+/// `Matrix` and both view types.
+///
+/// `row_*` reduce each row to one value and return a `rows x 1` column,
+/// `column_*` reduce each column and return a `1 x columns` row. Like `max`,
+/// the maxima are `None` when there are no columns, or rows, to take them over.
+pub trait Reduce<T: Float>: AsMatrixView<T> {
+    fn sum(&self) -> T {
+        self.view().iter().sum()
+    }
+
+    fn mean(&self) -> T {
+        let view = self.view();
+        view.iter().sum::<T>() / T::from_usize(view.rows * view.columns)
+    }
+
+    fn max(&self) -> Option<T> {
+        argmax_of(self.view().iter()).map(|(_, value)| value)
+    }
+
+    fn min(&self) -> Option<T> {
+        argmin_of(self.view().iter()).map(|(_, value)| value)
+    }
+
+    /// `(row, col)` of the largest element.
+    fn argmax(&self) -> Option<(usize, usize)> {
+        let columns = self.view().columns;
+        argmax_of(self.view().iter()).map(|(i, _)| (i / columns, i % columns))
+    }
+
+    /// `(row, col)` of the smallest element.
+    fn argmin(&self) -> Option<(usize, usize)> {
+        let columns = self.view().columns;
+        argmin_of(self.view().iter()).map(|(i, _)| (i / columns, i % columns))
+    }
+
+    /// Frobenius norm, square root of the sum of squares.
+    fn norm(&self) -> T {
+        self.view().iter().map(|x| x * x).sum::<T>().sqrt()
+    }
+
+    fn norm_l1(&self) -> T {
+        self.view().iter().map(|x| x.abs()).sum()
+    }
+
+    fn row_sums(&self) -> Matrix<T> {
+        per_row(self.view(), |row| row.iter().sum())
+    }
+
+    fn row_means(&self) -> Matrix<T> {
+        per_row(self.view(), |row| row.mean())
+    }
+
+    fn row_max(&self) -> Option<Matrix<T>> {
+        let view = self.view();
+        (view.columns > 0).then(|| per_row(view, |row| row.max().unwrap_or(T::ZERO)))
+    }
+
+    fn row_norms(&self) -> Matrix<T> {
+        per_row(self.view(), |row| row.norm())
+    }
+
+    /// Column index of the largest element of every row, the predicted
+    /// class when rows are samples and columns are class scores.
+    fn row_argmax(&self) -> Option<Vec<usize>> {
+        let view = self.view();
+        (0..view.rows)
+            .map(|r| argmax_of(view.row(r).iter()).map(|(col, _)| col))
+            .collect::<Option<Vec<usize>>>()
+            .filter(|_| view.columns > 0)
+    }
+
+    fn column_sums(&self) -> Matrix<T> {
+        per_row(self.view().t(), |col| col.iter().sum())
+            .view()
+            .t()
+            .to_matrix()
+    }
+
+    fn column_means(&self) -> Matrix<T> {
+        per_row(self.view().t(), |col| col.mean())
+            .view()
+            .t()
+            .to_matrix()
+    }
+
+    fn column_max(&self) -> Option<Matrix<T>> {
+        self.view()
+            .t()
+            .row_max()
+            .map(|max| max.view().t().to_matrix())
+    }
+
+    fn column_norms(&self) -> Matrix<T> {
+        per_row(self.view().t(), |col| col.norm())
+            .view()
+            .t()
+            .to_matrix()
+    }
+
+    fn column_argmax(&self) -> Option<Vec<usize>> {
+        self.view().t().row_argmax()
+    }
+}
+
+impl<T: Float, M: AsMatrixView<T> + ?Sized> Reduce<T> for M {}
+
+fn per_row<T: Float>(view: MatrixView<T>, f: impl Fn(MatrixView<T>) -> T) -> Matrix<T> {
+    Matrix {
+        rows: view.rows,
+        columns: 1,
+        data: (0..view.rows).map(|r| f(view.row(r))).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reductions() {
+        let m: Matrix = Matrix::from_2d_vec(&vec![
+            vec![1., -4., 2.], //
+            vec![3., 0., 7.],  //
+        ]);
+
+        assert_eq!(m.sum(), 9.);
+        assert_eq!(m.mean(), 1.5);
+        assert_eq!(m.max(), Some(7.));
+        assert_eq!(m.min(), Some(-4.));
+        assert_eq!(m.argmax(), Some((1, 2)));
+        assert_eq!(m.argmin(), Some((0, 1)));
+        assert_eq!(m.norm_l1(), 17.);
+        assert_eq!(m.row(0).norm(), 21f32.sqrt());
+
+        assert_eq!(m.row_sums().data, vec![-1., 10.]);
+        assert_eq!(m.row_sums().shape(), (2, 1));
+        assert_eq!(m.column_sums().data, vec![4., -4., 9.]);
+        assert_eq!(m.column_sums().shape(), (1, 3));
+        assert_eq!(m.column_max().unwrap().data, vec![3., 0., 7.]);
+        assert_eq!(m.row_argmax(), Some(vec![2, 2]));
+        assert_eq!(m.column_argmax(), Some(vec![1, 1, 1]));
+
+        let empty: Matrix = Matrix::new(0, 3);
+        assert_eq!(empty.max(), None);
+        assert_eq!(empty.sum(), 0.);
+        assert_eq!(empty.row_max().unwrap().shape(), (0, 1));
+        assert_eq!(empty.row_argmax(), Some(vec![]));
+        assert!(empty.column_max().is_none());
+        assert_eq!(empty.column_argmax(), None);
+    }
+}
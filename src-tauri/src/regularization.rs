@@ -0,0 +1,119 @@
+//! Penalties on large weights and limits on gradient size.
+
+use crate::float::Float;
+use crate::layer::Parameter;
+use crate::neural_network::Matrix;
+
+/// `l1 * sum(|w|) + l2 * sum(w²)` added to the cost of a layer.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Regularization {
+    pub l1: f64,
+    pub l2: f64,
+}
+
+impl Regularization {
+    pub fn is_none(&self) -> bool {
+        self.l1 == 0. && self.l2 == 0.
+    }
+
+    pub fn penalty<T: Float>(&self, w: &Matrix<T>) -> T {
+        let (l1, l2) = (T::from_f64(self.l1), T::from_f64(self.l2));
+        w.data.iter().map(|&x| l1 * x.abs() + l2 * x * x).sum()
+    }
+
+    /// Adds the gradient of `penalty` at `w` to `grad`.
+    pub fn add_gradient<T: Float>(&self, w: &Matrix<T>, grad: &mut Matrix<T>) {
+        let l1 = T::from_f64(self.l1);
+        let l2 = T::from_f64(2. * self.l2);
+        grad.zip_with_inplace(w, |g, x| g + l1 * x.sign() + l2 * x);
+    }
+}
+
+/// Limit on the gradients applied before each optimizer step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clip {
+    /// Clamps every element to `-max..=max`.
+    Value(f64),
+    /// Scales all gradients together so their combined norm is at most this.
+    GlobalNorm(f64),
+}
+
+impl Clip {
+    /// Clips the gradients of `parameters` in place and returns their global
+    /// norm from before clipping.
+    pub fn apply<T: Float>(&self, parameters: &mut [Parameter<'_, T>]) -> T {
+        let norm = global_norm(parameters);
+
+        match *self {
+            Clip::Value(max) => {
+                let max = T::from_f64(max);
+                for p in parameters.iter_mut() {
+                    p.grad.map_inplace(|g| g.max(-max).min(max));
+                }
+            }
+            Clip::GlobalNorm(max) => {
+                let max = T::from_f64(max);
+                if norm > max {
+                    let scale = max / norm;
+                    for p in parameters.iter_mut() {
+                        p.grad.dotf(scale);
+                    }
+                }
+            }
+        }
+
+        norm
+    }
+}
+
+/// Norm of all gradients of `parameters` taken as one vector.
+pub fn global_norm<T: Float>(parameters: &[Parameter<'_, T>]) -> T {
+    parameters
+        .iter()
+        .flat_map(|p| p.grad.data.iter())
+        .map(|&g| g * g)
+        .sum::<T>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regularization_and_clipping() {
+        let w: Matrix<f64> = Matrix::from_rows([[1., -2.], [0., 3.]]);
+        let both = Regularization { l1: 0.5, l2: 0.25 };
+        assert_eq!(both.penalty(&w), 0.5 * 6. + 0.25 * 14.);
+
+        let mut grad = Matrix::new(2, 2);
+        both.add_gradient(&w, &mut grad);
+        assert_eq!(grad.data, vec![1., -1.5, 0., 2.]);
+        assert!(Regularization::default().is_none());
+
+        let (mut a, mut b) = (w.clone(), w.clone());
+        let mut grad_a: Matrix<f64> = Matrix::from_rows([[3., -4.]]);
+        let mut grad_b = Matrix::from_rows([[0., 12.]]);
+        let mut parameters = [
+            Parameter {
+                value: &mut a,
+                grad: &mut grad_a,
+                decay: true,
+            },
+            Parameter {
+                value: &mut b,
+                grad: &mut grad_b,
+                decay: true,
+            },
+        ];
+
+        assert_eq!(Clip::Value(5.).apply(&mut parameters), 13.);
+        assert_eq!(parameters[1].grad.data, vec![0., 5.]);
+
+        // 3, -4, 0, 5 has norm sqrt(50)
+        let norm = Clip::GlobalNorm(1.).apply(&mut parameters);
+        assert!((norm - 50f64.sqrt()).abs() < 1e-12);
+        assert!((global_norm(&parameters) - 1.).abs() < 1e-12);
+        assert!((parameters[0].grad.data[0] * 50f64.sqrt() - 3.).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,275 @@
+//! Learning rates that change over the course of training.
+
+use crate::error::ScheduleError;
+use std::f64::consts::PI;
+
+/// Queried by `Sequential::fit` and `NeuralNetwork::learn` before every
+/// optimizer step.
+pub trait Schedule: std::fmt::Debug + Send {
+    /// Rate for optimizer step `step`, counted from 0 over the whole run.
+    fn rate(&mut self, step: usize) -> f64;
+
+    /// Called with the training loss after every epoch.
+    fn epoch_end(&mut self, _loss: f64) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constant {
+    pub rate: f64,
+}
+
+impl Schedule for Constant {
+    fn rate(&mut self, _step: usize) -> f64 {
+        self.rate
+    }
+}
+
+/// Multiplies the rate by `factor` every `every` steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepDecay {
+    pub initial: f64,
+    pub factor: f64,
+    every: usize,
+}
+
+impl StepDecay {
+    /// Fails when `every` is 0.
+    pub fn try_new(initial: f64, factor: f64, every: usize) -> Result<StepDecay, ScheduleError> {
+        ScheduleError::check("StepDecay", "every", every)?;
+        Ok(StepDecay {
+            initial,
+            factor,
+            every,
+        })
+    }
+
+    pub fn new(initial: f64, factor: f64, every: usize) -> StepDecay {
+        StepDecay::try_new(initial, factor, every).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn every(&self) -> usize {
+        self.every
+    }
+}
+
+impl Schedule for StepDecay {
+    fn rate(&mut self, step: usize) -> f64 {
+        self.initial * self.factor.powf((step / self.every) as f64)
+    }
+}
+
+/// Multiplies the rate by `decay` every step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exponential {
+    pub initial: f64,
+    pub decay: f64,
+}
+
+impl Schedule for Exponential {
+    fn rate(&mut self, step: usize) -> f64 {
+        self.initial * self.decay.powf(step as f64)
+    }
+}
+
+/// Half cosine from `max` down to `min` over `period` steps, then back to
+/// `max`, each period `multiplier` times longer than the last. A `multiplier`
+/// of 0 counts as 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CosineRestarts {
+    pub max: f64,
+    pub min: f64,
+    period: usize,
+    pub multiplier: usize,
+}
+
+impl CosineRestarts {
+    /// Fails when `period` is 0.
+    pub fn try_new(
+        max: f64,
+        min: f64,
+        period: usize,
+        multiplier: usize,
+    ) -> Result<CosineRestarts, ScheduleError> {
+        ScheduleError::check("CosineRestarts", "period", period)?;
+        Ok(CosineRestarts {
+            max,
+            min,
+            period,
+            multiplier,
+        })
+    }
+
+    pub fn new(max: f64, min: f64, period: usize, multiplier: usize) -> CosineRestarts {
+        CosineRestarts::try_new(max, min, period, multiplier)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+}
+
+/// First step of period `k` when the first one is `period` steps long and
+/// each is `multiplier` times longer, saturating where it cannot matter.
+fn cycle_start(period: usize, multiplier: usize, k: u32) -> u128 {
+    let m = multiplier as u128;
+    (period as u128).saturating_mul(m.saturating_pow(k) - 1) / (m - 1)
+}
+
+impl Schedule for CosineRestarts {
+    fn rate(&mut self, step: usize) -> f64 {
+        let multiplier = self.multiplier.max(1);
+        let (step, period) = if multiplier == 1 {
+            (step % self.period, self.period as f64)
+        } else {
+            // period k starts at period * (m^k - 1) / (m - 1), solve for k in
+            // floating point and fix up the rounding on integers
+            let m = multiplier as f64;
+            let mut k = ((step as f64 * (m - 1.) / self.period as f64 + 1.).ln() / m.ln()) as u32;
+            while k > 0 && cycle_start(self.period, multiplier, k) > step as u128 {
+                k -= 1;
+            }
+            while cycle_start(self.period, multiplier, k + 1) <= step as u128 {
+                k += 1;
+            }
+
+            let start = cycle_start(self.period, multiplier, k) as usize;
+            (step - start, self.period as f64 * m.powi(k as i32))
+        };
+
+        let progress = step as f64 / period;
+        self.min + (self.max - self.min) * (1. + (PI * progress).cos()) / 2.
+    }
+}
+
+/// Ramps linearly up to the rate of `inner` over the first `steps` steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Warmup<S> {
+    pub steps: usize,
+    pub inner: S,
+}
+
+impl<S: Schedule> Schedule for Warmup<S> {
+    fn rate(&mut self, step: usize) -> f64 {
+        let rate = self.inner.rate(step);
+        if step < self.steps {
+            rate * (step + 1) as f64 / self.steps as f64
+        } else {
+            rate
+        }
+    }
+
+    fn epoch_end(&mut self, loss: f64) {
+        self.inner.epoch_end(loss);
+    }
+}
+
+/// Multiplies the rate by `factor` once the epoch loss has not improved for
+/// more than `patience` epochs, never going below `min`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReduceOnPlateau {
+    pub factor: f64,
+    pub patience: usize,
+    pub min: f64,
+    rate: f64,
+    best: f64,
+    epochs_without_improvement: usize,
+}
+
+impl ReduceOnPlateau {
+    pub fn new(rate: f64, factor: f64, patience: usize) -> ReduceOnPlateau {
+        ReduceOnPlateau {
+            factor,
+            patience,
+            min: 0.,
+            rate,
+            best: f64::INFINITY,
+            epochs_without_improvement: 0,
+        }
+    }
+}
+
+impl Schedule for ReduceOnPlateau {
+    fn rate(&mut self, _step: usize) -> f64 {
+        self.rate
+    }
+
+    fn epoch_end(&mut self, loss: f64) {
+        if loss < self.best {
+            self.best = loss;
+            self.epochs_without_improvement = 0;
+            return;
+        }
+
+        self.epochs_without_improvement += 1;
+        if self.epochs_without_improvement > self.patience {
+            self.rate = (self.rate * self.factor).max(self.min);
+            self.epochs_without_improvement = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates(schedule: &mut impl Schedule, steps: &[usize]) -> Vec<f64> {
+        steps.iter().map(|&step| schedule.rate(step)).collect()
+    }
+
+    #[test]
+    fn test_schedules() {
+        assert_eq!(rates(&mut Constant { rate: 0.1 }, &[0, 99]), vec![0.1, 0.1]);
+
+        let mut step = StepDecay::new(1., 0.5, 10);
+        assert_eq!(rates(&mut step, &[0, 9, 10, 25]), vec![1., 1., 0.5, 0.25]);
+
+        let mut exponential = Exponential {
+            initial: 2.,
+            decay: 0.5,
+        };
+        assert_eq!(rates(&mut exponential, &[0, 1, 3]), vec![2., 1., 0.25]);
+
+        // periods of 4 and 8 steps
+        let mut cosine = CosineRestarts::new(1., 0., 4, 2);
+        let got = rates(&mut cosine, &[0, 2, 4, 8, 12]);
+        for (got, expected) in got.iter().zip([1., 0.5, 1., 0.5, 1.]) {
+            assert!((got - expected).abs() < 1e-12, "{} != {}", got, expected);
+        }
+        // periods of 1, 3, 9 and 27 steps start at 0, 1, 4, 13 and 40
+        let mut tripling = CosineRestarts::new(1., 0., 1, 3);
+        let got = rates(&mut tripling, &[13, 39, 40]);
+        assert!(got[0] == 1. && got[1] < 0.01 && got[2] == 1.);
+        // a multiplier of 1 restarts every 4 steps, up to the last step count
+        let mut repeating = CosineRestarts::new(1., 0., 4, 1);
+        assert_eq!(repeating.rate(usize::MAX - 3), 1.);
+
+        let mut warmup = Warmup {
+            steps: 4,
+            inner: Constant { rate: 1. },
+        };
+        assert_eq!(rates(&mut warmup, &[0, 1, 3, 4]), vec![0.25, 0.5, 1., 1.]);
+
+        let mut plateau = ReduceOnPlateau::new(1., 0.5, 1);
+        for loss in [3., 2., 2., 2.5, 1., 1.] {
+            plateau.epoch_end(loss);
+        }
+        assert_eq!(plateau.rate(0), 0.5);
+        plateau.epoch_end(1.);
+        assert_eq!(plateau.rate(0), 0.25);
+
+        // no wrapping for step counts beyond i32
+        let big = 1 << 40;
+        assert_eq!(step.rate(big), 0.);
+        assert_eq!(exponential.rate(big), 0.);
+        cosine.multiplier = 1 << 20;
+        assert!(cosine.rate(usize::MAX) <= 1.);
+    }
+
+    #[test]
+    fn test_zero_steps_rejected() {
+        let err = CosineRestarts::try_new(1., 0., 0, 2).unwrap_err();
+        assert_eq!(err.to_string(), "CosineRestarts: period must be at least 1");
+        assert!(StepDecay::try_new(1., 0.5, 0).is_err());
+    }
+}
@@ -0,0 +1,373 @@
+//! Model built from a stack of `Layer`s, each feeding the next.
+
+use crate::data::RNG_SEED;
+use crate::error::ShapeError;
+use crate::float::Float;
+use crate::layer::{Dense, Layer, Parameter};
+use crate::loss::{Loss, Mse};
+use crate::matrix_view::{AsMatrixView, MatrixView};
+use crate::neural_network::{LayerSpec, Matrix};
+use crate::optimizer::Optimizer;
+use crate::regularization::Clip;
+use crate::sparse::CsrMatrix;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// `L` is the type of every layer, any `Layer` by default. `NeuralNetwork`
+/// uses `Dense` to keep access to the weights and activations.
+#[derive(Debug)]
+pub struct Sequential<T = f32, L: ?Sized = dyn Layer<T>> {
+    layers: Vec<Box<L>>,
+    /// `a[0]` is the input, `a[i + 1]` the output of layer `i`.
+    a: Vec<Matrix<T>>,
+    /// Cost gradient with respect to `a[i]`.
+    grad: Vec<Matrix<T>>,
+    loss: Box<dyn Loss<T>>,
+}
+
+impl<T: Float> Sequential<T> {
+    /// Model without layers, its output is its input.
+    pub fn new(inputs: usize) -> Self {
+        Sequential::with_inputs(inputs)
+    }
+
+    /// `Dense` layers from the same `arch` as `NeuralNetwork::new`.
+    pub fn from_arch<S: Into<LayerSpec> + Copy>(arch: &[S]) -> Self {
+        let arch: Vec<LayerSpec> = arch.iter().map(|&layer| layer.into()).collect();
+        assert!(arch.len() > 1);
+
+        let mut model = Sequential::new(arch[0].size);
+        for pair in arch.windows(2) {
+            model.push(Dense::new(pair[0].size, pair[1].size, pair[1].activation));
+        }
+        model
+    }
+
+    /// Appends `layer`, which has to take the current output size as input.
+    pub fn try_push(&mut self, layer: impl Layer<T> + 'static) -> Result<(), ShapeError> {
+        self.try_push_boxed(Box::new(layer))
+    }
+
+    pub fn push(&mut self, layer: impl Layer<T> + 'static) {
+        self.try_push(layer).unwrap_or_else(|err| panic!("{}", err));
+    }
+}
+
+impl<T: Float, L: Layer<T> + ?Sized> Sequential<T, L> {
+    /// Same as `new` for any layer type.
+    pub fn with_inputs(inputs: usize) -> Self {
+        assert!(inputs > 0);
+
+        Sequential {
+            layers: Vec::new(),
+            a: vec![Matrix::new(1, inputs)],
+            grad: vec![Matrix::new(1, inputs)],
+            loss: Box::new(Mse),
+        }
+    }
+
+    /// Same as `try_push` for a layer that is already boxed.
+    pub fn try_push_boxed(&mut self, layer: Box<L>) -> Result<(), ShapeError> {
+        let outputs = self.output().columns;
+        ShapeError::check("push", (1, outputs), (1, layer.input_size()))?;
+
+        self.a.push(Matrix::new(1, layer.output_size()));
+        self.grad.push(Matrix::new(1, layer.output_size()));
+        self.layers.push(layer);
+        Ok(())
+    }
+
+    pub fn push_boxed(&mut self, layer: Box<L>) {
+        self.try_push_boxed(layer)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// Replaces the loss behind `cost` and `backprop`, `Mse` by default.
+    pub fn set_loss(&mut self, loss: impl Loss<T> + 'static) {
+        self.loss = Box::new(loss);
+    }
+
+    pub fn loss(&self) -> &dyn Loss<T> {
+        self.loss.as_ref()
+    }
+
+    pub fn layers(&self) -> &[Box<L>] {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut [Box<L>] {
+        &mut self.layers
+    }
+
+    /// Uniform parameters in `low..high`, seeded from `RNG_SEED`.
+    pub fn rand(&mut self, low: T, high: T) {
+        self.rand_with(&mut StdRng::seed_from_u64(RNG_SEED), low, high);
+    }
+
+    pub fn rand_with(&mut self, rng: &mut impl Rng, low: T, high: T) {
+        for layer in self.layers.iter_mut() {
+            for p in layer.parameters_mut() {
+                p.value.fill_uniform(rng, low, high);
+            }
+        }
+    }
+
+    pub fn forward(&mut self) {
+        self.forward_from(0);
+    }
+
+    /// Runs a batch of sparse inputs, one sample per row, through every layer,
+    /// the first one only visits the non-zeros. The dense input is not kept,
+    /// so `backprop` still needs the rows as a `Matrix`.
+    pub fn try_forward_sparse(&mut self, input: &CsrMatrix<T>) -> Result<(), ShapeError> {
+        let inputs = self.a[0].columns;
+        ShapeError::check("forward_sparse", (input.rows, inputs), input.shape())?;
+
+        for m in self.a.iter_mut().chain(self.grad.iter_mut()) {
+            m.resize_rows(input.rows);
+        }
+        match self.layers.first_mut() {
+            Some(first) => first.forward_sparse(input, &mut self.a[1]),
+            None => self.a[0].copy_from(&input.to_dense()),
+        }
+        self.forward_from(1);
+        Ok(())
+    }
+
+    pub fn forward_sparse(&mut self, input: &CsrMatrix<T>) {
+        self.try_forward_sparse(input)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    fn forward_from(&mut self, start: usize) {
+        for (i, layer) in self.layers.iter_mut().enumerate().skip(start) {
+            let (input, output) = self.a.split_at_mut(i + 1);
+            layer.forward(&input[i], &mut output[0]);
+        }
+    }
+
+    /// Sets a batch of inputs, one sample per row.
+    pub fn try_input(&mut self, input: &impl AsMatrixView<T>) -> Result<(), ShapeError> {
+        let input = input.view();
+        ShapeError::check("input", (input.rows, self.a[0].columns), input.shape())?;
+
+        for m in self.a.iter_mut().chain(self.grad.iter_mut()) {
+            m.resize_rows(input.rows);
+        }
+        self.a[0].copy_from(&input);
+        Ok(())
+    }
+
+    pub fn input(&mut self, input: &impl AsMatrixView<T>) {
+        self.try_input(input)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    pub fn output(&self) -> &Matrix<T> {
+        &self.a[self.a.len() - 1]
+    }
+
+    /// Checks a dataset against the input and output sizes.
+    fn check_truth(
+        &self,
+        op: &'static str,
+        truth_in: &MatrixView<T>,
+        truth_out: &MatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        let inputs = self.a[0].columns;
+        let outputs = self.output().columns;
+
+        ShapeError::check(op, (truth_in.rows, inputs), truth_in.shape())?;
+        ShapeError::check(op, (truth_in.rows, outputs), truth_out.shape())
+    }
+
+    pub fn try_cost(
+        &mut self,
+        truth_in: &impl AsMatrixView<T>,
+        truth_out: &impl AsMatrixView<T>,
+    ) -> Result<T, ShapeError> {
+        let truth_in = truth_in.view();
+        let truth_out = truth_out.view();
+        self.check_truth("cost", &truth_in, &truth_out)?;
+
+        self.input(&truth_in);
+        self.forward();
+
+        Ok(self.loss.loss(self.output(), &truth_out) + self.penalty())
+    }
+
+    pub fn cost(&mut self, truth_in: &impl AsMatrixView<T>, truth_out: &impl AsMatrixView<T>) -> T {
+        self.try_cost(truth_in, truth_out)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Sum of the regularization penalties of every layer.
+    pub fn penalty(&self) -> T {
+        self.layers.iter().map(|layer| layer.penalty()).sum()
+    }
+
+    pub fn zero_grad(&mut self) {
+        for layer in self.layers.iter_mut() {
+            for p in layer.parameters_mut() {
+                p.grad.fill(T::ZERO);
+            }
+        }
+    }
+
+    /// Replaces the gradients of every layer with those of `cost` over the dataset.
+    pub fn try_backprop(
+        &mut self,
+        truth_in: &impl AsMatrixView<T>,
+        truth_out: &impl AsMatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        let truth_in = truth_in.view();
+        let truth_out = truth_out.view();
+        self.check_truth("backprop", &truth_in, &truth_out)?;
+
+        self.zero_grad();
+        self.input(&truth_in);
+        self.forward();
+
+        let last = self.a.len() - 1;
+        self.loss
+            .gradient(&self.a[last], &truth_out, &mut self.grad[last]);
+
+        for (l, layer) in self.layers.iter_mut().enumerate().rev() {
+            let (input_grad, grad) = self.grad.split_at_mut(l + 1);
+            layer.backward(&self.a[l], &self.a[l + 1], &mut grad[0], &mut input_grad[l]);
+            layer.add_penalty_gradient();
+        }
+
+        Ok(())
+    }
+
+    pub fn backprop(&mut self, truth_in: &impl AsMatrixView<T>, truth_out: &impl AsMatrixView<T>) {
+        self.try_backprop(truth_in, truth_out)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// Steps every parameter along the gradients of `backprop` with
+    /// `optimizer`, which has to be used with this model only.
+    pub fn learn(&mut self, optimizer: &mut impl Optimizer<T>) {
+        optimizer.step(&mut self.parameters_mut());
+    }
+
+    /// Clips the gradients of `backprop`, returns their global norm from
+    /// before clipping.
+    pub fn clip_gradients(&mut self, clip: Clip) -> T {
+        clip.apply(&mut self.parameters_mut())
+    }
+
+    /// Parameters of every layer in order, paired with their gradients.
+    pub(crate) fn parameters_mut(&mut self) -> Vec<Parameter<'_, T>> {
+        self.layers
+            .iter_mut()
+            .flat_map(|layer| layer.parameters_mut())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activation::Builtin::*;
+    use crate::loss::{BinaryCrossEntropy, SoftmaxCrossEntropy};
+    use crate::neural_network::NeuralNetwork;
+    use crate::optimizer::Adam;
+    use crate::regularization::Regularization;
+
+    fn assert_gradients(
+        model: &mut Sequential<f64>,
+        truth_in: &Matrix<f64>,
+        truth_out: &Matrix<f64>,
+    ) {
+        model.backprop(truth_in, truth_out);
+
+        let eps = 1e-6;
+        for l in 0..model.layers().len() {
+            let params = model.layers()[l].parameters().len();
+            for p in 0..params {
+                let len = model.layers()[l].parameters()[p].data.len();
+                for index in 0..len {
+                    let nudge = |model: &mut Sequential<f64>, delta: f64| {
+                        model.layers_mut()[l].parameters_mut()[p].value.data[index] += delta;
+                        model.cost(truth_in, truth_out)
+                    };
+                    let plus = nudge(model, eps);
+                    let minus = nudge(model, -2. * eps);
+                    nudge(model, eps);
+
+                    let expected = (plus - minus) / (2. * eps);
+                    let got = model.layers()[l].gradients()[p].data[index];
+                    assert!(
+                        (expected - got).abs() < 1e-6,
+                        "{}: {} != {}",
+                        model.loss().name(),
+                        expected,
+                        got
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sequential_gradients() {
+        let truth_in: Matrix<f64> = Matrix::from_fn(4, 2, |r, c| (r * 2 + c) as f64 / 4. - 0.8);
+        let truth_out = Matrix::from_fn(4, 2, |r, c| if r % 2 == c { 1. } else { 0. });
+
+        let arch = [(2, Linear), (3, Tanh), (4, Relu), (2, Sigmoid)];
+        let mut model = Sequential::from_arch(&arch);
+        model.rand(-1., 1.);
+        assert_gradients(&mut model, &truth_in, &truth_out);
+        model.set_loss(BinaryCrossEntropy);
+        assert_gradients(&mut model, &truth_in, &truth_out);
+        model.set_loss(SoftmaxCrossEntropy);
+        assert_gradients(&mut model, &truth_in, &truth_out);
+        model.set_loss(Mse);
+
+        // same seed, same parameters and outputs as the fixed dense network,
+        // up to the rounding of the nudges above
+        let mut nn = NeuralNetwork::new(&arch);
+        nn.rand(-1., 1.);
+        let cost = nn.cost(&truth_in, &truth_out);
+        assert!((cost - model.cost(&truth_in, &truth_out)).abs() < 1e-12);
+
+        let mut adam = Adam::new(0.01);
+        for _ in 0..10 {
+            model.backprop(&truth_in, &truth_out);
+            model.learn(&mut adam);
+        }
+        assert!(model.cost(&truth_in, &truth_out) < cost);
+
+        // penalties count in both the cost and the gradients
+        let mut model = Sequential::new(2);
+        let regularization = Regularization { l1: 0.01, l2: 0.1 };
+        model.push(Dense::new(2, 3, Tanh).with_regularization(regularization));
+        model.push(Dense::new(3, 2, Sigmoid));
+        model.rand(-1., 1.);
+        assert!(model.penalty() > 0.);
+        assert_gradients(&mut model, &truth_in, &truth_out);
+
+        let mut model = Sequential::<f32>::new(2);
+        assert!(model.try_push(Dense::new(3, 1, Linear)).is_err());
+        model.push(Dense::new(2, 1, Linear));
+        assert_eq!(model.output().columns, 1);
+    }
+
+    #[test]
+    fn test_sequential_sparse_input() {
+        // one-hot rows
+        let truth_in: Matrix<f64> = Matrix::from_fn(4, 6, |r, c| (c == r * 5 % 6) as u8 as f64);
+        let mut model = Sequential::from_arch(&[(6, Linear), (3, Tanh), (2, Sigmoid)]);
+        model.rand(-1., 1.);
+        model.input(&truth_in);
+        model.forward();
+        let expected = model.output().clone();
+
+        model.forward_sparse(&CsrMatrix::from_dense(&truth_in));
+        assert_eq!(model.output().data, expected.data);
+
+        let wrong = CsrMatrix::from_dense(&Matrix::<f64>::new(4, 5));
+        assert!(model.try_forward_sparse(&wrong).is_err());
+    }
+}
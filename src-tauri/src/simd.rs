@@ -0,0 +1,128 @@
+//! AVX + FMA versions of the `Float` slice kernels, enabled by the `simd` feature.
+//! Each entry point takes the `Cpu` detected by its caller and falls back to
+//! plain loops without AVX and FMA.
+
+use crate::float::Cpu;
+use std::arch::x86_64::*;
+
+pub(crate) fn has_avx_fma() -> bool {
+    is_x86_feature_detected!("avx") && is_x86_feature_detected!("fma")
+}
+
+pub fn mul_add_slice_f32(cpu: Cpu, dst: &mut [f32], scale: f32, src: &[f32]) {
+    assert_eq!(dst.len(), src.len());
+
+    if cpu.avx_fma {
+        // SAFETY: `Cpu::detect` found avx and fma, lengths are equal
+        unsafe { mul_add_slice_f32_avx(dst, scale, src) }
+    } else {
+        for (d, &s) in dst.iter_mut().zip(src) {
+            *d += scale * s;
+        }
+    }
+}
+
+#[target_feature(enable = "avx,fma")]
+unsafe fn mul_add_slice_f32_avx(dst: &mut [f32], scale: f32, src: &[f32]) {
+    let n = dst.len() / 8 * 8;
+    let s = _mm256_set1_ps(scale);
+
+    for i in (0..n).step_by(8) {
+        let d = _mm256_loadu_ps(dst.as_ptr().add(i));
+        let x = _mm256_loadu_ps(src.as_ptr().add(i));
+        _mm256_storeu_ps(dst.as_mut_ptr().add(i), _mm256_fmadd_ps(s, x, d));
+    }
+    for i in n..dst.len() {
+        dst[i] += scale * src[i];
+    }
+}
+
+pub fn dot_slice_f32(cpu: Cpu, a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+
+    if cpu.avx_fma {
+        // SAFETY: `Cpu::detect` found avx and fma, lengths are equal
+        unsafe { dot_slice_f32_avx(a, b) }
+    } else {
+        a.iter().zip(b).map(|(&x, &y)| x * y).sum()
+    }
+}
+
+#[target_feature(enable = "avx,fma")]
+unsafe fn dot_slice_f32_avx(a: &[f32], b: &[f32]) -> f32 {
+    let n = a.len() / 8 * 8;
+    let mut acc = _mm256_setzero_ps();
+
+    for i in (0..n).step_by(8) {
+        let x = _mm256_loadu_ps(a.as_ptr().add(i));
+        let y = _mm256_loadu_ps(b.as_ptr().add(i));
+        acc = _mm256_fmadd_ps(x, y, acc);
+    }
+
+    let mut lanes = [0f32; 8];
+    _mm256_storeu_ps(lanes.as_mut_ptr(), acc);
+    let mut sum: f32 = lanes.iter().sum();
+    for i in n..a.len() {
+        sum += a[i] * b[i];
+    }
+    sum
+}
+
+pub fn mul_add_slice_f64(cpu: Cpu, dst: &mut [f64], scale: f64, src: &[f64]) {
+    assert_eq!(dst.len(), src.len());
+
+    if cpu.avx_fma {
+        // SAFETY: `Cpu::detect` found avx and fma, lengths are equal
+        unsafe { mul_add_slice_f64_avx(dst, scale, src) }
+    } else {
+        for (d, &s) in dst.iter_mut().zip(src) {
+            *d += scale * s;
+        }
+    }
+}
+
+#[target_feature(enable = "avx,fma")]
+unsafe fn mul_add_slice_f64_avx(dst: &mut [f64], scale: f64, src: &[f64]) {
+    let n = dst.len() / 4 * 4;
+    let s = _mm256_set1_pd(scale);
+
+    for i in (0..n).step_by(4) {
+        let d = _mm256_loadu_pd(dst.as_ptr().add(i));
+        let x = _mm256_loadu_pd(src.as_ptr().add(i));
+        _mm256_storeu_pd(dst.as_mut_ptr().add(i), _mm256_fmadd_pd(s, x, d));
+    }
+    for i in n..dst.len() {
+        dst[i] += scale * src[i];
+    }
+}
+
+pub fn dot_slice_f64(cpu: Cpu, a: &[f64], b: &[f64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+
+    if cpu.avx_fma {
+        // SAFETY: `Cpu::detect` found avx and fma, lengths are equal
+        unsafe { dot_slice_f64_avx(a, b) }
+    } else {
+        a.iter().zip(b).map(|(&x, &y)| x * y).sum()
+    }
+}
+
+#[target_feature(enable = "avx,fma")]
+unsafe fn dot_slice_f64_avx(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() / 4 * 4;
+    let mut acc = _mm256_setzero_pd();
+
+    for i in (0..n).step_by(4) {
+        let x = _mm256_loadu_pd(a.as_ptr().add(i));
+        let y = _mm256_loadu_pd(b.as_ptr().add(i));
+        acc = _mm256_fmadd_pd(x, y, acc);
+    }
+
+    let mut lanes = [0f64; 4];
+    _mm256_storeu_pd(lanes.as_mut_ptr(), acc);
+    let mut sum: f64 = lanes.iter().sum();
+    for i in n..a.len() {
+        sum += a[i] * b[i];
+    }
+    sum
+}
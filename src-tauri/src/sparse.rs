@@ -0,0 +1,223 @@
+//! Compressed sparse row matrices for mostly-zero inputs such as one-hot or
+//! bag-of-words rows.
+
+use crate::error::ShapeError;
+use crate::float::{Cpu, Float};
+use crate::matrix_view::AsMatrixView;
+use crate::neural_network::Matrix;
+
+/// Row `r` holds the non-zeros `values[row_ptr[r]..row_ptr[r + 1]]`, at the
+/// matching positions of `col_indices`, sorted by column.
+#[derive(Debug, Clone)]
+pub struct CsrMatrix<T = f32> {
+    pub rows: usize,
+    pub columns: usize,
+    row_ptr: Vec<usize>,
+    col_indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T: Float> CsrMatrix<T> {
+    /// Keeps every element that is not exactly zero.
+    pub fn from_dense(m: &impl AsMatrixView<T>) -> CsrMatrix<T> {
+        let m = m.view();
+        let mut sparse = CsrMatrix {
+            rows: m.rows,
+            columns: m.columns,
+            row_ptr: vec![0],
+            col_indices: Vec::new(),
+            values: Vec::new(),
+        };
+
+        for row in 0..m.rows {
+            for col in 0..m.columns {
+                let value = m.get(row, col);
+                if value != T::ZERO {
+                    sparse.col_indices.push(col);
+                    sparse.values.push(value);
+                }
+            }
+            sparse.row_ptr.push(sparse.values.len());
+        }
+
+        sparse
+    }
+
+    /// Builds from `(row, col, value)` entries in any order, duplicates are summed.
+    pub fn from_triplets(
+        rows: usize,
+        columns: usize,
+        triplets: impl IntoIterator<Item = (usize, usize, T)>,
+    ) -> CsrMatrix<T> {
+        let mut triplets: Vec<_> = triplets.into_iter().collect();
+        triplets.sort_by_key(|&(row, col, _)| (row, col));
+
+        let mut sparse = CsrMatrix {
+            rows,
+            columns,
+            row_ptr: vec![0; rows + 1],
+            col_indices: Vec::with_capacity(triplets.len()),
+            values: Vec::with_capacity(triplets.len()),
+        };
+
+        let mut last = None;
+        for (row, col, value) in triplets {
+            assert!(row < rows && col < columns, "triplet out of bounds");
+
+            if last == Some((row, col)) {
+                *sparse.values.last_mut().unwrap() += value;
+            } else {
+                sparse.col_indices.push(col);
+                sparse.values.push(value);
+                sparse.row_ptr[row + 1] += 1;
+                last = Some((row, col));
+            }
+        }
+        for row in 0..rows {
+            sparse.row_ptr[row + 1] += sparse.row_ptr[row];
+        }
+
+        sparse
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    /// Number of stored elements.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// `(col, value)` of the stored elements of `row`.
+    pub fn row(&self, row: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        let range = self.row_ptr[row]..self.row_ptr[row + 1];
+        self.col_indices[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[range].iter().copied())
+    }
+
+    pub fn to_dense(&self) -> Matrix<T> {
+        let mut m = Matrix::new(self.rows, self.columns);
+        for row in 0..self.rows {
+            for (col, value) in self.row(row) {
+                m[(row, col)] = value;
+            }
+        }
+        m
+    }
+}
+
+impl<T: Float> Matrix<T> {
+    /// Writes `a · b` into `self`, only the non-zeros of `a` are visited.
+    pub fn try_sparse_dot_into(
+        &mut self,
+        a: &CsrMatrix<T>,
+        b: &impl AsMatrixView<T>,
+    ) -> Result<(), ShapeError> {
+        let b = b.view();
+        ShapeError::check("sparse_dot", (a.columns, b.columns), b.shape())?;
+        ShapeError::check("sparse_dot", (a.rows, b.columns), self.shape())?;
+
+        let cpu = Cpu::detect();
+        self.fill(T::ZERO);
+        for row in 0..a.rows {
+            let start = row * self.columns;
+            let c_row = &mut self.data[start..start + self.columns];
+
+            for (k, value) in a.row(row) {
+                let b_row = b.row(k);
+                match b_row.as_slice() {
+                    Some(b_row) => T::mul_add_slice(cpu, c_row, value, b_row),
+                    None => {
+                        for (c, b) in c_row.iter_mut().zip(b_row.iter()) {
+                            *c += value * b;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn sparse_dot_into(&mut self, a: &CsrMatrix<T>, b: &impl AsMatrixView<T>) {
+        self.try_sparse_dot_into(a, b)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// Writes `aᵀ · b` into `self`, only the non-zeros of `b` are visited.
+    pub fn try_transposed_sparse_dot_into(
+        &mut self,
+        a: &impl AsMatrixView<T>,
+        b: &CsrMatrix<T>,
+    ) -> Result<(), ShapeError> {
+        let a = a.view();
+        ShapeError::check("transposed_sparse_dot", (a.rows, b.columns), b.shape())?;
+        ShapeError::check(
+            "transposed_sparse_dot",
+            (a.columns, b.columns),
+            self.shape(),
+        )?;
+
+        let columns = self.columns;
+        self.fill(T::ZERO);
+        for row in 0..b.rows {
+            let a_row = a.row(row);
+            for (col, value) in b.row(row) {
+                // column `col` of self
+                let c_col = self.data[col..].iter_mut().step_by(columns);
+                for (c, x) in c_col.zip(a_row.iter()) {
+                    *c += x * value;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn transposed_sparse_dot_into(&mut self, a: &impl AsMatrixView<T>, b: &CsrMatrix<T>) {
+        self.try_transposed_sparse_dot_into(a, b)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_matches_dense() {
+        let dense: Matrix = Matrix::from_fn(5, 7, |r, c| {
+            if (r * 7 + c) % 4 == 0 {
+                (r + c) as f32
+            } else {
+                0.
+            }
+        });
+        let sparse = CsrMatrix::from_dense(&dense);
+        assert_eq!(sparse.nnz(), 8);
+        assert_eq!(sparse.to_dense().data, dense.data);
+
+        let b: Matrix = Matrix::from_fn(7, 3, |r, c| r as f32 - c as f32);
+        let mut expected = Matrix::new(5, 3);
+        expected.dot_into(&dense, &b);
+        let mut got = Matrix::new(5, 3);
+        got.sparse_dot_into(&sparse, &b);
+        assert_eq!(got.data, expected.data);
+        got.sparse_dot_into(&sparse, &b.t().to_matrix().t());
+        assert_eq!(got.data, expected.data);
+
+        let a: Matrix = Matrix::from_fn(5, 2, |r, c| (r * 2 + c) as f32);
+        let mut expected = Matrix::new(2, 7);
+        expected.transposed_dot_into(&a, &dense);
+        let mut got = Matrix::new(2, 7);
+        got.transposed_sparse_dot_into(&a, &sparse);
+        assert_eq!(got.data, expected.data);
+        assert!(got.try_sparse_dot_into(&sparse, &a).is_err());
+
+        let t = CsrMatrix::from_triplets(2, 2, [(1, 0, 2.), (0, 1, 1.), (1, 0, 3.)]);
+        assert_eq!(t.to_dense().data, vec![0., 1., 5., 0.]);
+    }
+}
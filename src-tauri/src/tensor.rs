@@ -0,0 +1,288 @@
+//! N-dimensional strided tensor, `Matrix` is the 2-D special case.
+
+use crate::error::TensorError;
+use crate::float::Float;
+use crate::neural_network::Matrix;
+
+/// Owned n-dimensional array.
+///
+/// `strides[i]` is how far apart in `data` two neighbours along axis `i` are,
+/// so `permute` only shuffles shape and strides and leaves `data` alone.
+#[derive(Debug, Clone)]
+pub struct Tensor<T = f32> {
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+    data: Vec<T>,
+}
+
+/// Row-major strides, the last axis is contiguous.
+fn contiguous_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1] * shape[i + 1];
+    }
+    strides
+}
+
+impl<T: Float> Tensor<T> {
+    pub fn new(shape: &[usize]) -> Tensor<T> {
+        Tensor {
+            shape: shape.to_vec(),
+            strides: contiguous_strides(shape),
+            data: vec![T::ZERO; shape.iter().product()],
+        }
+    }
+
+    /// Wraps row-major `data`, which must hold exactly `shape.iter().product()` elements.
+    pub fn try_from_vec(shape: &[usize], data: Vec<T>) -> Result<Tensor<T>, TensorError> {
+        let len: usize = shape.iter().product();
+        if data.len() != len {
+            return Err(TensorError {
+                op: "from_vec",
+                expected: vec![len],
+                actual: vec![data.len()],
+            });
+        }
+
+        Ok(Tensor {
+            shape: shape.to_vec(),
+            strides: contiguous_strides(shape),
+            data,
+        })
+    }
+
+    pub fn from_vec(shape: &[usize], data: Vec<T>) -> Tensor<T> {
+        Tensor::try_from_vec(shape, data).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn offset(&self, index: &[usize]) -> usize {
+        assert_eq!(index.len(), self.ndim(), "tensor index has the wrong rank");
+
+        index
+            .iter()
+            .zip(&self.shape)
+            .zip(&self.strides)
+            .map(|((&i, &len), &stride)| {
+                assert!(i < len, "tensor index out of bounds");
+                i * stride
+            })
+            .sum()
+    }
+
+    pub fn get(&self, index: &[usize]) -> T {
+        self[index]
+    }
+
+    pub fn set(&mut self, index: &[usize], value: T) {
+        self[index] = value;
+    }
+
+    pub fn is_contiguous(&self) -> bool {
+        self.strides == contiguous_strides(&self.shape)
+    }
+
+    /// Elements in row-major order of the current shape.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        let mut index = vec![0; self.ndim()];
+        let mut remaining = self.len();
+
+        std::iter::from_fn(move || {
+            if remaining == 0 {
+                return None;
+            }
+            remaining -= 1;
+
+            let value = self[&index[..]];
+            // odometer increment, last axis fastest
+            for axis in (0..index.len()).rev() {
+                index[axis] += 1;
+                if index[axis] < self.shape[axis] {
+                    break;
+                }
+                index[axis] = 0;
+            }
+            Some(value)
+        })
+    }
+
+    /// Same elements with row-major strides, copies only when needed.
+    pub fn into_contiguous(self) -> Tensor<T> {
+        if self.is_contiguous() {
+            return self;
+        }
+
+        let data = self.iter().collect();
+        Tensor {
+            strides: contiguous_strides(&self.shape),
+            shape: self.shape,
+            data,
+        }
+    }
+
+    /// Reinterprets the elements, in row-major order, under a new shape with
+    /// the same number of elements.
+    pub fn try_reshape(self, shape: &[usize]) -> Result<Tensor<T>, TensorError> {
+        if shape.iter().product::<usize>() != self.len() {
+            return Err(TensorError {
+                op: "reshape",
+                expected: self.shape,
+                actual: shape.to_vec(),
+            });
+        }
+
+        let mut tensor = self.into_contiguous();
+        tensor.shape = shape.to_vec();
+        tensor.strides = contiguous_strides(shape);
+        Ok(tensor)
+    }
+
+    pub fn reshape(self, shape: &[usize]) -> Tensor<T> {
+        self.try_reshape(shape)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Reorders the axes, axis `i` of the result is axis `axes[i]` of `self`.
+    /// No data is moved.
+    pub fn try_permute(mut self, axes: &[usize]) -> Result<Tensor<T>, TensorError> {
+        let mut seen = vec![false; self.ndim()];
+        let valid = axes.len() == self.ndim()
+            && axes
+                .iter()
+                .all(|&axis| axis < seen.len() && !std::mem::replace(&mut seen[axis], true));
+        if !valid {
+            return Err(TensorError {
+                op: "permute",
+                expected: (0..self.ndim()).collect(),
+                actual: axes.to_vec(),
+            });
+        }
+
+        self.shape = axes.iter().map(|&axis| self.shape[axis]).collect();
+        self.strides = axes.iter().map(|&axis| self.strides[axis]).collect();
+        Ok(self)
+    }
+
+    pub fn permute(self, axes: &[usize]) -> Tensor<T> {
+        self.try_permute(axes)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Drops every axis of length 1.
+    pub fn squeeze(mut self) -> Tensor<T> {
+        (self.shape, self.strides) = self
+            .shape
+            .iter()
+            .zip(&self.strides)
+            .filter(|(&len, _)| len != 1)
+            .unzip();
+        self
+    }
+
+    /// Inserts an axis of length 1 before `axis`.
+    pub fn unsqueeze(mut self, axis: usize) -> Tensor<T> {
+        assert!(axis <= self.ndim(), "unsqueeze axis out of range");
+
+        let stride = self.strides.get(axis).map_or(1, |&s| s * self.shape[axis]);
+        self.shape.insert(axis, 1);
+        self.strides.insert(axis, stride);
+        self
+    }
+
+    /// Converts a 2-D tensor, copying only when it is not row-major.
+    pub fn try_into_matrix(self) -> Result<Matrix<T>, TensorError> {
+        if self.ndim() != 2 {
+            return Err(TensorError {
+                op: "into_matrix",
+                expected: vec![2],
+                actual: vec![self.ndim()],
+            });
+        }
+
+        let tensor = self.into_contiguous();
+        Ok(Matrix {
+            rows: tensor.shape[0],
+            columns: tensor.shape[1],
+            data: tensor.data,
+        })
+    }
+
+    pub fn into_matrix(self) -> Matrix<T> {
+        self.try_into_matrix()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl<T: Float> From<Matrix<T>> for Tensor<T> {
+    fn from(m: Matrix<T>) -> Self {
+        Tensor::from_vec(&[m.rows, m.columns], m.data)
+    }
+}
+
+impl<T: Float> std::ops::Index<&[usize]> for Tensor<T> {
+    type Output = T;
+
+    fn index(&self, index: &[usize]) -> &T {
+        &self.data[self.offset(index)]
+    }
+}
+
+impl<T: Float> std::ops::IndexMut<&[usize]> for Tensor<T> {
+    fn index_mut(&mut self, index: &[usize]) -> &mut T {
+        let offset = self.offset(index);
+        &mut self.data[offset]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tensor_shapes() {
+        let t: Tensor = Tensor::from_vec(&[2, 3, 4], (0..24).map(|x| x as f32).collect());
+        assert_eq!(t.strides(), &[12, 4, 1]);
+        assert_eq!(t.get(&[1, 2, 3]), 23.);
+
+        let p = t.clone().permute(&[2, 0, 1]);
+        assert_eq!(p.shape(), &[4, 2, 3]);
+        assert_eq!(p.get(&[3, 1, 2]), t.get(&[1, 2, 3]));
+        assert!(!p.is_contiguous());
+        let r = p.reshape(&[4, 6]);
+        assert_eq!(
+            r.iter().take(7).collect::<Vec<_>>(),
+            vec![0., 4., 8., 12., 16., 20., 1.]
+        );
+
+        assert!(t.clone().try_reshape(&[5, 5]).is_err());
+        assert!(t.clone().try_permute(&[0, 0, 1]).is_err());
+
+        let s = t.clone().reshape(&[1, 24, 1]).squeeze();
+        assert_eq!(s.shape(), &[24]);
+        assert_eq!(s.unsqueeze(0).shape(), &[1, 24]);
+
+        let m: Matrix = Matrix::from_fn(2, 3, |r, c| (r * 3 + c) as f32);
+        let back = Tensor::from(m.clone()).permute(&[1, 0]).into_matrix();
+        assert_eq!(back.data, m.t().to_matrix().data);
+        assert!(t.try_into_matrix().is_err());
+    }
+}
@@ -0,0 +1,351 @@
+//! Mini-batch training loop for `Sequential` models.
+
+use crate::data::{EPOCHS, RNG_SEED};
+use crate::error::{ShapeError, TrainError};
+use crate::float::Float;
+use crate::layer::Layer;
+use crate::matrix_view::{AsMatrixView, MatrixView};
+use crate::neural_network::Matrix;
+use crate::optimizer::Optimizer;
+use crate::regularization::Clip;
+use crate::schedule::Schedule;
+use crate::sequential::Sequential;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrainConfig {
+    pub epochs: usize,
+    /// Rows per optimizer step, the last batch of an epoch may be smaller.
+    pub batch_size: usize,
+    /// Reorders the rows before every epoch.
+    pub shuffle: bool,
+    pub seed: u64,
+    /// Applied to the gradients of every batch before the optimizer step.
+    pub clip: Option<Clip>,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        TrainConfig {
+            epochs: EPOCHS,
+            batch_size: 32,
+            shuffle: true,
+            seed: RNG_SEED,
+            clip: None,
+        }
+    }
+}
+
+/// Passed to the progress callback of `Sequential::fit`, epochs count from 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progress<T> {
+    /// Loss of one batch, regularization penalties included, before the
+    /// step it caused at learning rate `rate`.
+    Batch {
+        epoch: usize,
+        batch: usize,
+        loss: T,
+        rate: f64,
+    },
+    /// Mean batch loss over the epoch, weighted by batch size, and the rate
+    /// of its last step.
+    Epoch { epoch: usize, loss: T, rate: f64 },
+}
+
+/// Copies `rows` of `m`, in that order.
+fn gather<T: Float>(m: &MatrixView<T>, rows: &[usize]) -> Matrix<T> {
+    Matrix::from_fn(rows.len(), m.columns, |row, col| m.get(rows[row], col))
+}
+
+impl<T: Float, L: Layer<T> + ?Sized> Sequential<T, L> {
+    /// Trains on `config.batch_size` rows at a time with `optimizer`, at the
+    /// rate `schedule` gives for every step, and returns the loss of every epoch.
+    ///
+    /// The schedule's rate replaces the rate `optimizer` was created with
+    /// before every step.
+    pub fn try_fit(
+        &mut self,
+        truth_in: &impl AsMatrixView<T>,
+        truth_out: &impl AsMatrixView<T>,
+        optimizer: &mut impl Optimizer<T>,
+        schedule: &mut impl Schedule,
+        config: &TrainConfig,
+        mut progress: impl FnMut(Progress<T>),
+    ) -> Result<Vec<T>, TrainError> {
+        if config.batch_size == 0 {
+            return Err(TrainError::ZeroBatchSize);
+        }
+
+        let truth_in = truth_in.view();
+        let truth_out = truth_out.view();
+        let rows = truth_in.rows;
+        ShapeError::check("fit", (rows, truth_out.columns), truth_out.shape())?;
+        if rows == 0 {
+            return Err(TrainError::EmptyDataset);
+        }
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut order: Vec<usize> = (0..rows).collect();
+        let mut losses = Vec::with_capacity(config.epochs);
+        let mut step = 0;
+        let mut rate = optimizer.learning_rate();
+
+        for epoch in 1..=config.epochs {
+            if config.shuffle {
+                order.shuffle(&mut rng);
+            }
+
+            let mut epoch_loss = T::ZERO;
+            for (batch, indices) in order.chunks(config.batch_size).enumerate() {
+                let batch_in = gather(&truth_in, indices);
+                let batch_out = gather(&truth_out, indices);
+
+                self.try_backprop(&batch_in, &batch_out)?;
+                let loss = self.loss().loss(self.output(), &batch_out.view()) + self.penalty();
+                if let Some(clip) = config.clip {
+                    self.clip_gradients(clip);
+                }
+                rate = schedule.rate(step);
+                optimizer.set_learning_rate(rate);
+                self.learn(optimizer);
+                step += 1;
+
+                epoch_loss += loss * T::from_usize(indices.len());
+                progress(Progress::Batch {
+                    epoch,
+                    batch,
+                    loss,
+                    rate,
+                });
+            }
+
+            let loss = epoch_loss / T::from_usize(rows);
+            losses.push(loss);
+            schedule.epoch_end(loss.to_f64());
+            progress(Progress::Epoch { epoch, loss, rate });
+        }
+
+        Ok(losses)
+    }
+
+    pub fn fit(
+        &mut self,
+        truth_in: &impl AsMatrixView<T>,
+        truth_out: &impl AsMatrixView<T>,
+        optimizer: &mut impl Optimizer<T>,
+        schedule: &mut impl Schedule,
+        config: &TrainConfig,
+        progress: impl FnMut(Progress<T>),
+    ) -> Vec<T> {
+        self.try_fit(truth_in, truth_out, optimizer, schedule, config, progress)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activation::Builtin::*;
+    use crate::layer::Parameter;
+    use crate::optimizer::{Adam, Sgd};
+    use crate::regularization::global_norm;
+    use crate::schedule::{Constant, Exponential, StepDecay};
+
+    #[test]
+    fn test_fit_mini_batches() {
+        // y = x0 - 2 x1 + 0.5
+        let truth_in: Matrix<f64> =
+            Matrix::from_fn(50, 2, |r, c| ((r * 7 + c * 3) % 11) as f64 / 5. - 1.);
+        let truth_out = Matrix::from_fn(50, 1, |r, _| {
+            truth_in.get(r, 0) - 2. * truth_in.get(r, 1) + 0.5
+        });
+
+        let config = TrainConfig {
+            epochs: 200,
+            batch_size: 16,
+            ..TrainConfig::default()
+        };
+        let train = || {
+            let mut model = Sequential::from_arch(&[(2, Linear), (1, Linear)]);
+            model.rand(-1., 1.);
+            // 4 steps per epoch, halved after epoch 100
+            let mut schedule = StepDecay::new(0.05, 0.5, 400);
+            let mut batches = Vec::new();
+            let mut rates = Vec::new();
+            let losses = model.fit(
+                &truth_in,
+                &truth_out,
+                &mut Adam::new(0.05),
+                &mut schedule,
+                &config,
+                |p| match p {
+                    Progress::Batch {
+                        epoch: 1,
+                        batch,
+                        loss,
+                        ..
+                    } => batches.push((batch, loss)),
+                    Progress::Epoch { rate, .. } => rates.push(rate),
+                    _ => {}
+                },
+            );
+            assert_eq!((rates[99], rates[100]), (0.05, 0.025));
+            (losses, batches)
+        };
+
+        let (losses, batches) = train();
+        // 16 + 16 + 16 + 2
+        assert_eq!(
+            batches.iter().map(|b| b.0).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        let weighted = batches
+            .iter()
+            .zip([16., 16., 16., 2.])
+            .map(|(b, n)| b.1 * n)
+            .sum::<f64>();
+        assert!((losses[0] - weighted / 50.).abs() < 1e-12);
+        assert!(losses[199] < 1e-6 && losses[199] < losses[0]);
+
+        // same seed, same shuffles
+        assert_eq!(train().0, losses);
+
+        let mut model = Sequential::from_arch(&[(2, Linear), (1, Linear)]);
+        let mut fit = |truth_in: &Matrix<f64>, truth_out: &Matrix<f64>, batch_size| {
+            let config = TrainConfig {
+                batch_size,
+                ..config
+            };
+            let mut schedule = StepDecay::new(0.05, 0.5, 400);
+            model
+                .try_fit(
+                    truth_in,
+                    truth_out,
+                    &mut Adam::new(0.05),
+                    &mut schedule,
+                    &config,
+                    |_| {},
+                )
+                .unwrap_err()
+        };
+        assert_eq!(fit(&truth_in, &truth_out, 0), TrainError::ZeroBatchSize);
+        let empty = fit(&Matrix::new(0, 2), &Matrix::new(0, 1), 16);
+        assert_eq!(empty, TrainError::EmptyDataset);
+        let err = fit(&truth_in, &Matrix::new(49, 1), 16);
+        assert_eq!(
+            err,
+            TrainError::Shape(ShapeError {
+                op: "fit",
+                expected: (50, 1),
+                actual: (49, 1)
+            })
+        );
+    }
+
+    /// Gradient descent remembering the rate and gradient norm of every step.
+    #[derive(Debug)]
+    struct Recording {
+        sgd: Sgd<f64>,
+        rates: Vec<f64>,
+        norms: Vec<f64>,
+    }
+
+    impl Recording {
+        fn new() -> Recording {
+            Recording {
+                sgd: Sgd::new(1.),
+                rates: Vec::new(),
+                norms: Vec::new(),
+            }
+        }
+    }
+
+    impl Optimizer<f64> for Recording {
+        fn step(&mut self, parameters: &mut [Parameter<'_, f64>]) {
+            self.rates.push(self.sgd.rate);
+            self.norms.push(global_norm(parameters));
+            self.sgd.step(parameters);
+        }
+
+        fn learning_rate(&self) -> f64 {
+            self.sgd.rate
+        }
+
+        fn set_learning_rate(&mut self, rate: f64) {
+            self.sgd.rate = rate;
+        }
+    }
+
+    #[test]
+    fn test_fit_schedule_rates() {
+        let truth_in: Matrix<f64> = Matrix::from_fn(10, 2, |r, c| (r + c) as f64 / 10.);
+        let truth_out = Matrix::from_fn(10, 1, |r, _| r as f64 / 10.);
+
+        let mut model = Sequential::from_arch(&[(2, Linear), (1, Linear)]);
+        let mut optimizer = Recording::new();
+        let mut schedule = Exponential {
+            initial: 0.1,
+            decay: 0.5,
+        };
+        let config = TrainConfig {
+            epochs: 2,
+            batch_size: 4,
+            ..TrainConfig::default()
+        };
+
+        let mut reported = Vec::new();
+        model.fit(
+            &truth_in,
+            &truth_out,
+            &mut optimizer,
+            &mut schedule,
+            &config,
+            |p| {
+                if let Progress::Batch { rate, .. } = p {
+                    reported.push(rate);
+                }
+            },
+        );
+
+        // 3 batches per epoch, the constructor's rate is never used
+        let expected: Vec<f64> = (0..6).map(|step| 0.1 * 0.5f64.powi(step)).collect();
+        assert_eq!(optimizer.rates, expected);
+        assert_eq!(reported, expected);
+    }
+
+    #[test]
+    fn test_fit_clips_gradients() {
+        let truth_in: Matrix<f64> = Matrix::from_fn(10, 2, |r, c| (r + c) as f64);
+        let truth_out = Matrix::from_fn(10, 1, |r, _| 10. * r as f64);
+
+        let norms = |clip| {
+            let mut model = Sequential::from_arch(&[(2, Linear), (1, Linear)]);
+            let mut optimizer = Recording::new();
+            let config = TrainConfig {
+                epochs: 3,
+                batch_size: 5,
+                clip,
+                ..TrainConfig::default()
+            };
+            let mut schedule = Constant { rate: 1e-3 };
+            model.fit(
+                &truth_in,
+                &truth_out,
+                &mut optimizer,
+                &mut schedule,
+                &config,
+                |_| {},
+            );
+            optimizer.norms
+        };
+
+        assert!(norms(None).iter().all(|&norm| norm > 1.));
+        let clipped = norms(Some(Clip::GlobalNorm(1.)));
+        assert!(clipped.iter().all(|&norm| (norm - 1.).abs() < 1e-12));
+        let clipped = norms(Some(Clip::Value(0.1)));
+        // 3 clamped gradients, 2 weights and a bias
+        assert!(clipped
+            .iter()
+            .all(|&norm| norm <= 0.1 * 3f64.sqrt() + 1e-12));
+    }
+}